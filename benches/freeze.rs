@@ -0,0 +1,45 @@
+use nodio::prelude::*;
+use std::{hint::black_box, time::Instant};
+
+const COUNT: usize = 10_000;
+const ITERATIONS: u32 = 20;
+
+struct Tree(#[allow(dead_code)] usize);
+struct Child;
+
+fn bench(name: &str, f: impl Fn()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    println!("{name}: {:?} per iteration", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let mut graph = Graph::default();
+    let nodes = (0..COUNT)
+        .map(|index| graph.insert(Tree(index)))
+        .collect::<Vec<_>>();
+    for (index, node) in nodes.iter().enumerate() {
+        for step in [1, 7, 31, 127] {
+            graph.relate::<Child>(*node, nodes[(index * step + step) % COUNT]);
+        }
+    }
+
+    bench("iterate neighbors of mutable graph", || {
+        for node in &nodes {
+            for target in graph.relations_outgoing::<Child>(*node) {
+                black_box(target);
+            }
+        }
+    });
+    let graph = graph.freeze();
+    bench("iterate neighbors of frozen graph", || {
+        for node in &nodes {
+            for target in graph.relations_outgoing::<Child>(*node) {
+                black_box(target);
+            }
+        }
+    });
+}
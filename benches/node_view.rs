@@ -0,0 +1,62 @@
+use nodio::prelude::*;
+use std::{hint::black_box, time::Instant};
+
+const COUNT: usize = 10_000;
+const ITERATIONS: u32 = 20;
+
+struct Tree(#[allow(dead_code)] usize);
+struct Child;
+struct Parent;
+struct Effect;
+
+fn bench(name: &str, f: impl Fn()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    println!("{name}: {:?} per iteration", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let mut graph = Graph::default();
+    let nodes = (0..COUNT)
+        .map(|index| graph.insert(Tree(index)))
+        .collect::<Vec<_>>();
+    for (index, node) in nodes.iter().enumerate() {
+        for step in [1, 7, 31, 127] {
+            let target = nodes[(index * step + step) % COUNT];
+            graph.relate::<Child>(*node, target);
+            graph.relate::<Parent>(target, *node);
+            graph.relate::<Effect>(*node, target);
+        }
+    }
+
+    bench(
+        "query neighbors under three categories with separate calls",
+        || {
+            for node in &nodes {
+                black_box(graph.relations_outgoing::<Child>(*node).count());
+                black_box(graph.relations_incoming::<Child>(*node).count());
+                black_box(graph.relations_outgoing::<Parent>(*node).count());
+                black_box(graph.relations_incoming::<Parent>(*node).count());
+                black_box(graph.relations_outgoing::<Effect>(*node).count());
+                black_box(graph.relations_incoming::<Effect>(*node).count());
+            }
+        },
+    );
+    bench(
+        "query neighbors under three categories with node view",
+        || {
+            for node in &nodes {
+                let view = graph.node_view(*node);
+                black_box(view.outgoing::<Child>().count());
+                black_box(view.incoming::<Child>().count());
+                black_box(view.outgoing::<Parent>().count());
+                black_box(view.incoming::<Parent>().count());
+                black_box(view.outgoing::<Effect>().count());
+                black_box(view.incoming::<Effect>().count());
+            }
+        },
+    );
+}
@@ -0,0 +1,70 @@
+use nodio::{
+    prelude::*,
+    third_party::intuicio_core::{registry::Registry, types::struct_type::NativeStructBuilder},
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const CAPACITY: usize = 1024;
+const ITERATIONS: u32 = 100;
+
+#[derive(Default)]
+struct Kind<const N: usize>(#[allow(dead_code)] usize);
+
+macro_rules! kinds {
+    ($($n:literal)*) => {
+        (
+            Registry::default()$(.with_type(NativeStructBuilder::new::<Kind<$n>>().build()))*,
+            [$(insert::<$n> as fn(&mut Graph)),*],
+        )
+    };
+}
+
+fn insert<const N: usize>(graph: &mut Graph) {
+    black_box(graph.insert(Kind::<N>(N)));
+}
+
+fn bench(name: &str, setup: impl Fn() -> Graph, f: impl Fn(&mut Graph)) {
+    let mut elapsed = Duration::default();
+    for _ in 0..ITERATIONS {
+        let mut graph = setup();
+        let start = Instant::now();
+        f(&mut graph);
+        elapsed += start.elapsed();
+    }
+    println!("{name}: {:?} per iteration", elapsed / ITERATIONS);
+}
+
+fn main() {
+    let (registry, inserts) = kinds!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+        32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61
+        62 63
+    );
+    let types = registry.types().map(|type_| &**type_).collect::<Vec<_>>();
+
+    bench(
+        "first insert of many types without preparation",
+        || Graph::default().with_new_arena_capacity(CAPACITY),
+        |graph| {
+            for insert in &inserts {
+                insert(graph);
+            }
+        },
+    );
+    bench(
+        "first insert of many types with preparation",
+        || {
+            let mut graph = Graph::default().with_new_arena_capacity(CAPACITY);
+            graph.prepare_types(&types, CAPACITY);
+            graph
+        },
+        |graph| {
+            for insert in &inserts {
+                insert(graph);
+            }
+        },
+    );
+}
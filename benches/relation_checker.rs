@@ -0,0 +1,50 @@
+use nodio::prelude::*;
+use std::{hint::black_box, time::Instant};
+
+const COUNT: usize = 1_000;
+const CHECKS: usize = 1_000_000;
+const ITERATIONS: u32 = 20;
+
+struct Tree(#[allow(dead_code)] usize);
+struct Child;
+struct Parent;
+struct Effect;
+
+fn bench(name: &str, f: impl Fn()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    println!("{name}: {:?} per iteration", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let mut graph = Graph::default();
+    let nodes = (0..COUNT)
+        .map(|index| graph.insert(Tree(index)))
+        .collect::<Vec<_>>();
+    for (index, node) in nodes.iter().enumerate() {
+        for step in [1, 7, 31, 127] {
+            let target = nodes[(index * step + step) % COUNT];
+            graph.relate::<Child>(*node, target);
+            graph.relate::<Parent>(target, *node);
+            graph.relate::<Effect>(*node, target);
+        }
+    }
+    let pairs = (0..CHECKS)
+        .map(|index| (nodes[index % COUNT], nodes[(index * 7 + 1) % COUNT]))
+        .collect::<Vec<_>>();
+
+    bench("check relations with are_related", || {
+        for (from, to) in &pairs {
+            black_box(graph.are_related::<Child>(*from, *to));
+        }
+    });
+    bench("check relations with relation checker", || {
+        let checker = graph.relation_checker::<Child>();
+        for (from, to) in &pairs {
+            black_box(checker.check(*from, *to));
+        }
+    });
+}
@@ -0,0 +1,35 @@
+use nodio::prelude::*;
+use std::{hint::black_box, time::Instant};
+
+const COUNT: usize = 100_000;
+const ITERATIONS: u32 = 20;
+
+#[derive(Default)]
+struct Tree(#[allow(dead_code)] [usize; 32]);
+
+fn bench(name: &str, f: impl Fn()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    println!("{name}: {:?} per iteration", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    bench("insert without reserve", || {
+        let mut graph = Graph::default().with_new_arena_capacity(16);
+        for index in 0..COUNT {
+            black_box(graph.insert(Tree([index; 32])));
+        }
+    });
+    bench("insert with reserve", || {
+        let mut graph = Graph::default().with_new_arena_capacity(16);
+        graph.reserve::<Tree>(COUNT);
+        let capacity = graph.node_capacity::<Tree>();
+        for index in 0..COUNT {
+            black_box(graph.insert(Tree([index; 32])));
+        }
+        assert_eq!(graph.node_capacity::<Tree>(), capacity);
+    });
+}
@@ -0,0 +1,277 @@
+use crate::{
+    graph::Graph,
+    prefab::{PrefabDataType, PrefabError, PrefabRelationsPairItem, deserialize_to_buffer},
+};
+use intuicio_core::{registry::Registry, types::TypeQuery};
+use intuicio_data::{non_zero_dealloc, type_hash::TypeHash};
+use intuicio_framework_arena::AnyIndex;
+use intuicio_framework_serde::{Intermediate, SerializationRegistry};
+use serde::{Deserialize, Serialize};
+
+/// Serializable graph mutation, meant to be recorded and replayed for
+/// replication or undo.
+/// Types and relation categories are identified by their registry names.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GraphCommand {
+    /// Inserts new node with serialized data.
+    Insert {
+        data_type: PrefabDataType,
+        data: Intermediate,
+    },
+    /// Removes node.
+    Remove { node: PrefabRelationsPairItem },
+    /// Relates two nodes with relation category.
+    Relate {
+        category: PrefabDataType,
+        source: PrefabRelationsPairItem,
+        target: PrefabRelationsPairItem,
+    },
+    /// Unrelates two nodes with relation category.
+    Unrelate {
+        category: PrefabDataType,
+        source: PrefabRelationsPairItem,
+        target: PrefabRelationsPairItem,
+    },
+    /// Removes all nodes and relations.
+    Clear,
+}
+
+impl GraphCommand {
+    /// Creates command inserting node with provided data.
+    ///
+    /// # Arguments
+    /// * `value` - The value to be inserted.
+    /// * `serialization` - The serialization registry to use for serialization.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Returns
+    /// A result containing the command or an error.
+    pub fn insert<T: 'static>(
+        value: &T,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<Self, PrefabError> {
+        let data_type = data_type(TypeHash::of::<T>(), registry)?;
+        let data = unsafe {
+            serialization.dynamic_serialize_from(
+                TypeHash::of::<T>(),
+                (value as *const T).cast::<u8>(),
+                registry,
+            )
+        }
+        .map_err(|_| PrefabError::CouldNotSerializeType {
+            type_name: data_type.type_name.to_owned(),
+            module_name: data_type.module_name.to_owned(),
+        })?;
+        Ok(Self::Insert { data_type, data })
+    }
+
+    /// Creates command removing node.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to remove.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Returns
+    /// A result containing the command or an error.
+    pub fn remove(index: AnyIndex, registry: &Registry) -> Result<Self, PrefabError> {
+        Ok(Self::Remove {
+            node: node_item(index, registry)?,
+        })
+    }
+
+    /// Creates command relating two nodes with specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing the command or an error.
+    pub fn relate<T>(
+        from: AnyIndex,
+        to: AnyIndex,
+        registry: &Registry,
+    ) -> Result<Self, PrefabError> {
+        Ok(Self::Relate {
+            category: data_type(TypeHash::of::<T>(), registry)?,
+            source: node_item(from, registry)?,
+            target: node_item(to, registry)?,
+        })
+    }
+
+    /// Creates command unrelating two nodes with specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing the command or an error.
+    pub fn unrelate<T>(
+        from: AnyIndex,
+        to: AnyIndex,
+        registry: &Registry,
+    ) -> Result<Self, PrefabError> {
+        Ok(Self::Unrelate {
+            category: data_type(TypeHash::of::<T>(), registry)?,
+            source: node_item(from, registry)?,
+            target: node_item(to, registry)?,
+        })
+    }
+}
+
+impl Graph {
+    /// Starts recording commands applied with `apply` method.
+    /// Mutations made directly through other graph methods are not recorded.
+    /// Recording restarts if it was already started.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Default::default());
+    }
+
+    /// Stops recording commands.
+    ///
+    /// # Returns
+    /// A list of commands applied since recording started.
+    pub fn stop_recording(&mut self) -> Vec<GraphCommand> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    /// Checks if graph records applied commands.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Applies command to the graph, recording it if recording was started.
+    /// Node indices are allocated deterministically, so replaying recorded
+    /// commands on an empty graph reproduces the same node indices.
+    ///
+    /// # Arguments
+    /// * `command` - The command to apply.
+    /// * `serialization` - The serialization registry to use for deserialization.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Returns
+    /// A result containing the index of inserted node, if any, or an error.
+    pub fn apply(
+        &mut self,
+        command: GraphCommand,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<Option<AnyIndex>, PrefabError> {
+        let mut result = None;
+        match &command {
+            GraphCommand::Insert { data_type, data } => {
+                let type_ = registry
+                    .find_type(type_query(data_type))
+                    .ok_or_else(|| type_not_found(data_type))?;
+                // Data is deserialized into temporary buffer first, so failed
+                // insert does not touch arena and replay stays deterministic.
+                unsafe {
+                    let layout = *type_.layout();
+                    let buffer = deserialize_to_buffer(&type_, data, serialization, registry)?;
+                    let arena =
+                        self.nodes
+                            .ensure_arena_raw(type_.type_hash(), layout, type_.finalizer());
+                    let (index, memory) = arena.allocate();
+                    buffer.copy_to_nonoverlapping(memory, layout.size());
+                    non_zero_dealloc(buffer, layout);
+                    result = Some(AnyIndex::new(index, type_.type_hash()));
+                }
+            }
+            GraphCommand::Remove { node } => {
+                self.remove(resolve_node(node, registry)?)?;
+            }
+            GraphCommand::Relate {
+                category,
+                source,
+                target,
+            } => {
+                let type_hash = resolve_type(category, registry)?;
+                let from = resolve_node(source, registry)?;
+                let to = resolve_node(target, registry)?;
+                self.relate_raw(from, to, type_hash);
+            }
+            GraphCommand::Unrelate {
+                category,
+                source,
+                target,
+            } => {
+                let type_hash = resolve_type(category, registry)?;
+                let from = resolve_node(source, registry)?;
+                let to = resolve_node(target, registry)?;
+                self.unrelate_raw(from, to, type_hash);
+            }
+            GraphCommand::Clear => {
+                self.clear();
+            }
+        }
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(command);
+        }
+        Ok(result)
+    }
+}
+
+fn data_type(type_hash: TypeHash, registry: &Registry) -> Result<PrefabDataType, PrefabError> {
+    let type_ = registry
+        .find_type(TypeQuery {
+            type_hash: Some(type_hash),
+            ..Default::default()
+        })
+        .ok_or(PrefabError::CouldNotFindType(type_hash))?;
+    Ok(PrefabDataType {
+        type_name: type_.name().to_owned(),
+        module_name: type_.module_name().map(|name| name.to_owned()),
+    })
+}
+
+fn node_item(index: AnyIndex, registry: &Registry) -> Result<PrefabRelationsPairItem, PrefabError> {
+    Ok(PrefabRelationsPairItem {
+        data_type: data_type(index.type_hash(), registry)?,
+        index: index.index(),
+    })
+}
+
+fn type_query(data_type: &PrefabDataType) -> TypeQuery<'_> {
+    TypeQuery {
+        name: Some(data_type.type_name.as_str().into()),
+        module_name: data_type
+            .module_name
+            .as_ref()
+            .map(|name| name.as_str().into()),
+        ..Default::default()
+    }
+}
+
+fn resolve_type(data_type: &PrefabDataType, registry: &Registry) -> Result<TypeHash, PrefabError> {
+    registry
+        .find_type(type_query(data_type))
+        .map(|type_| type_.type_hash())
+        .ok_or_else(|| type_not_found(data_type))
+}
+
+fn type_not_found(data_type: &PrefabDataType) -> PrefabError {
+    PrefabError::CouldNotFindTypeByName {
+        type_name: data_type.type_name.to_owned(),
+        module_name: data_type.module_name.to_owned(),
+    }
+}
+
+fn resolve_node(
+    item: &PrefabRelationsPairItem,
+    registry: &Registry,
+) -> Result<AnyIndex, PrefabError> {
+    Ok(AnyIndex::new(
+        item.index,
+        resolve_type(&item.data_type, registry)?,
+    ))
+}
@@ -0,0 +1,70 @@
+use crate::{graph::Graph, utils::type_name};
+use intuicio_core::registry::Registry;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+const COLORS: &[&str] = &[
+    "black", "red", "blue", "green", "orange", "purple", "brown", "magenta", "cyan", "gray",
+];
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Graph {
+    /// Exports graph as GraphViz DOT document.
+    /// Each node is labeled with its type name, each relation becomes an edge
+    /// labeled with its category type name and colored by its category, so
+    /// self-loops and relations of multiple categories between the same nodes
+    /// are rendered as distinct edges.
+    ///
+    /// # Arguments
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Returns
+    /// A string containing DOT document.
+    pub fn to_dot(&self, registry: &Registry) -> String {
+        let mut indices = self.indices().collect::<Vec<_>>();
+        indices.sort();
+        let ids = indices
+            .iter()
+            .enumerate()
+            .map(|(id, index)| (*index, format!("n{id}")))
+            .collect::<HashMap<_, _>>();
+        let mut categories = BTreeMap::<String, Vec<_>>::default();
+        for (type_hash, from, to) in self.relations() {
+            let (Some(source), Some(target)) = (ids.get(&from), ids.get(&to)) else {
+                continue;
+            };
+            categories
+                .entry(type_name(registry, type_hash))
+                .or_default()
+                .push((source, target));
+        }
+        let mut result = String::default();
+        let _ = writeln!(result, "digraph G {{");
+        for index in &indices {
+            let _ = writeln!(
+                result,
+                r#"    {} [label="{}"];"#,
+                ids[index],
+                escape(&type_name(registry, index.type_hash()))
+            );
+        }
+        for (category, (name, edges)) in categories.iter_mut().enumerate() {
+            edges.sort();
+            let name = escape(name);
+            let color = COLORS[category % COLORS.len()];
+            for (source, target) in edges.iter() {
+                let _ = writeln!(
+                    result,
+                    r#"    {source} -> {target} [label="{name}", color="{color}", fontcolor="{color}"];"#
+                );
+            }
+        }
+        let _ = writeln!(result, "}}");
+        result
+    }
+}
@@ -0,0 +1,156 @@
+use intuicio_data::type_hash::TypeHash;
+use intuicio_framework_arena::AnyIndex;
+use std::collections::{HashMap, HashSet};
+
+/// Identifier of graph that owns a node, chosen by the user.
+pub type GraphId = usize;
+
+/// Index of a node in a specific graph.
+pub type ExternalIndex = (GraphId, AnyIndex);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ExternalRelationsTable {
+    outgoing: HashMap<ExternalIndex, HashSet<ExternalIndex>>,
+    incoming: HashMap<ExternalIndex, HashSet<ExternalIndex>>,
+}
+
+/// Relations between nodes that live in different graphs.
+/// Graphs themselves are not referenced, only their user-provided identifiers,
+/// so relations spanning graph boundaries can be stored without merging them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExternalRelations {
+    relations: HashMap<TypeHash, ExternalRelationsTable>,
+}
+
+impl ExternalRelations {
+    /// Relates two nodes from possibly different graphs with specified
+    /// relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The graph id and index of the source node.
+    /// * `to` - The graph id and index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn relate_external<T>(&mut self, from: ExternalIndex, to: ExternalIndex) {
+        let table = self.relations.entry(TypeHash::of::<T>()).or_default();
+        table.outgoing.entry(from).or_default().insert(to);
+        table.incoming.entry(to).or_default().insert(from);
+    }
+
+    /// Unrelates two nodes from possibly different graphs with specified
+    /// relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The graph id and index of the source node.
+    /// * `to` - The graph id and index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn unrelate_external<T>(&mut self, from: ExternalIndex, to: ExternalIndex) {
+        let type_hash = TypeHash::of::<T>();
+        if let Some(table) = self.relations.get_mut(&type_hash) {
+            if let Some(set) = table.outgoing.get_mut(&from) {
+                set.remove(&to);
+                if set.is_empty() {
+                    table.outgoing.remove(&from);
+                }
+            }
+            if let Some(set) = table.incoming.get_mut(&to) {
+                set.remove(&from);
+                if set.is_empty() {
+                    table.incoming.remove(&to);
+                }
+            }
+            if table.outgoing.is_empty() && table.incoming.is_empty() {
+                self.relations.remove(&type_hash);
+            }
+        }
+    }
+
+    /// Checks if two nodes are related with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The graph id and index of the source node.
+    /// * `to` - The graph id and index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn are_related_external<T>(&self, from: ExternalIndex, to: ExternalIndex) -> bool {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .and_then(|table| table.outgoing.get(&from))
+            .map(|set| set.contains(&to))
+            .unwrap_or_default()
+    }
+
+    /// Gets iterator over all outgoing relations from the specified source node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The graph id and index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the graph ids and indices of the target nodes.
+    pub fn relations_outgoing_external<T>(
+        &self,
+        from: ExternalIndex,
+    ) -> impl Iterator<Item = ExternalIndex> + '_ {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .and_then(|table| table.outgoing.get(&from))
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
+
+    /// Gets iterator over all incoming relations to the specified target node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `to` - The graph id and index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the graph ids and indices of the source nodes.
+    pub fn relations_incoming_external<T>(
+        &self,
+        to: ExternalIndex,
+    ) -> impl Iterator<Item = ExternalIndex> + '_ {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .and_then(|table| table.incoming.get(&to))
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
+
+    /// Removes all relations that have any endpoint in specified graph.
+    ///
+    /// # Arguments
+    /// * `graph` - The id of the graph.
+    pub fn remove_graph(&mut self, graph: GraphId) {
+        for table in self.relations.values_mut() {
+            table.outgoing.retain(|(id, _), _| *id != graph);
+            table.incoming.retain(|(id, _), _| *id != graph);
+            table.outgoing.retain(|_, set| {
+                set.retain(|(id, _)| *id != graph);
+                !set.is_empty()
+            });
+            table.incoming.retain(|_, set| {
+                set.retain(|(id, _)| *id != graph);
+                !set.is_empty()
+            });
+        }
+        self.relations
+            .retain(|_, table| !table.outgoing.is_empty() || !table.incoming.is_empty());
+    }
+
+    /// Removes all external relations.
+    pub fn clear(&mut self) {
+        self.relations.clear();
+    }
+}
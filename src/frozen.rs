@@ -0,0 +1,284 @@
+use crate::{
+    graph::Graph,
+    query::{QueryFetch, QueryIter},
+};
+use intuicio_data::{lifetime::ValueReadAccess, type_hash::TypeHash};
+use intuicio_framework_arena::{AnyIndex, ArenaError};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Relations of single category stored in compressed sparse row form.
+/// Rows are ordered by node index, so row of a node is found with binary
+/// search and its neighbors are a contiguous slice.
+#[derive(Debug, Default)]
+struct CsrTable {
+    nodes: Vec<AnyIndex>,
+    outgoing_offsets: Vec<usize>,
+    outgoing: Vec<AnyIndex>,
+    incoming_offsets: Vec<usize>,
+    incoming: Vec<AnyIndex>,
+}
+
+impl CsrTable {
+    fn new(edges: impl Iterator<Item = (AnyIndex, AnyIndex)>) -> Self {
+        let mut edges = edges.collect::<Vec<_>>();
+        let mut nodes = edges
+            .iter()
+            .flat_map(|(from, to)| [*from, *to])
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        edges.sort();
+        let (outgoing_offsets, outgoing) = Self::compress(&nodes, &edges);
+        let mut reversed = edges
+            .into_iter()
+            .map(|(from, to)| (to, from))
+            .collect::<Vec<_>>();
+        reversed.sort();
+        let (incoming_offsets, incoming) = Self::compress(&nodes, &reversed);
+        Self {
+            nodes,
+            outgoing_offsets,
+            outgoing,
+            incoming_offsets,
+            incoming,
+        }
+    }
+
+    fn compress(nodes: &[AnyIndex], edges: &[(AnyIndex, AnyIndex)]) -> (Vec<usize>, Vec<AnyIndex>) {
+        let mut offsets = Vec::with_capacity(nodes.len() + 1);
+        let mut edge = 0;
+        offsets.push(0);
+        for node in nodes {
+            while edge < edges.len() && edges[edge].0 == *node {
+                edge += 1;
+            }
+            offsets.push(edge);
+        }
+        (offsets, edges.iter().map(|(_, to)| *to).collect())
+    }
+
+    fn row(&self, index: AnyIndex) -> Option<usize> {
+        self.nodes.binary_search(&index).ok()
+    }
+
+    fn contains(&self, from: AnyIndex, to: AnyIndex) -> bool {
+        self.outgoing(from).binary_search(&to).is_ok()
+    }
+
+    fn outgoing(&self, from: AnyIndex) -> &[AnyIndex] {
+        self.row(from)
+            .map(|row| &self.outgoing[self.outgoing_offsets[row]..self.outgoing_offsets[row + 1]])
+            .unwrap_or_default()
+    }
+
+    fn incoming(&self, to: AnyIndex) -> &[AnyIndex] {
+        self.row(to)
+            .map(|row| &self.incoming[self.incoming_offsets[row]..self.incoming_offsets[row + 1]])
+            .unwrap_or_default()
+    }
+}
+
+/// Immutable form of a graph optimized for read-heavy workloads.
+/// Relations of every category are additionally stored as compressed sparse
+/// rows, so iterating neighbors walks contiguous memory instead of hash sets.
+/// Queries are performed on the wrapped graph, which is left untouched.
+pub struct FrozenGraph {
+    graph: Graph,
+    tables: HashMap<TypeHash, CsrTable>,
+}
+
+impl Graph {
+    /// Freezes the graph into immutable, read optimized form.
+    ///
+    /// # Returns
+    /// A new `FrozenGraph` instance.
+    pub fn freeze(self) -> FrozenGraph {
+        let tables = self
+            .relations
+            .iter()
+            .map(|(type_hash, relations)| (*type_hash, CsrTable::new(relations.iter_outgoing())))
+            .collect();
+        FrozenGraph {
+            graph: self,
+            tables,
+        }
+    }
+}
+
+impl FrozenGraph {
+    /// Turns frozen graph back into mutable graph.
+    ///
+    /// # Returns
+    /// The `Graph` instance this frozen graph was made from.
+    pub fn thaw(self) -> Graph {
+        self.graph
+    }
+
+    /// Gets the wrapped graph for read-only access.
+    ///
+    /// # Returns
+    /// A reference to the wrapped graph.
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Checks if the graph contains a node at the specified index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to check.
+    ///
+    /// # Returns
+    /// `true` if the node exists, `false` otherwise.
+    pub fn contains(&self, index: AnyIndex) -> bool {
+        self.graph.contains(index)
+    }
+
+    /// Checks if the node at the specified index is of the specified type.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to check.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the node.
+    ///
+    /// # Returns
+    /// `true` if the node is of the specified type, `false` otherwise.
+    pub fn is<T>(&self, index: AnyIndex) -> bool {
+        self.graph.is::<T>(index)
+    }
+
+    /// Returns read-only access to the node at the specified index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to read.
+    ///
+    /// # Returns
+    /// A `Result` containing the read access to the node or an error.
+    pub fn read<T>(&self, index: AnyIndex) -> Result<ValueReadAccess<'_, T>, ArenaError> {
+        self.graph.read(index)
+    }
+
+    /// Checks if two nodes are related with specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// `true` if the nodes are related, `false` otherwise.
+    pub fn are_related<T>(&self, from: AnyIndex, to: AnyIndex) -> bool {
+        self.tables
+            .get(&TypeHash::of::<T>())
+            .map(|table| table.contains(from, to))
+            .unwrap_or_default()
+    }
+
+    /// Gets iterator over all outgoing relations from the specified source node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_outgoing<T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_outgoing_raw(from, TypeHash::of::<T>())
+    }
+
+    /// Gets iterator over all outgoing relations from the specified source node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `type_hash` - The type hash of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_outgoing_raw(
+        &self,
+        from: AnyIndex,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.tables
+            .get(&type_hash)
+            .map(|table| table.outgoing(from))
+            .unwrap_or_default()
+            .iter()
+            .copied()
+    }
+
+    /// Gets iterator over all incoming relations to the specified target node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the source nodes.
+    pub fn relations_incoming<T>(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.tables
+            .get(&TypeHash::of::<T>())
+            .map(|table| table.incoming(to))
+            .unwrap_or_default()
+            .iter()
+            .copied()
+    }
+
+    /// Gets traverse iterator over all relations from the specified source node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse<T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        let type_hash = TypeHash::of::<T>();
+        let mut stack = VecDeque::from([from]);
+        let mut visited = HashSet::<AnyIndex>::default();
+        std::iter::from_fn(move || {
+            while let Some(index) = stack.pop_front() {
+                if !visited.insert(index) {
+                    continue;
+                }
+                stack.extend(self.relations_outgoing_raw(index, type_hash));
+                return Some(index);
+            }
+            None
+        })
+    }
+
+    /// Performs a query on the graph starting from the specified index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to start the query from.
+    ///
+    /// # Type Parameters
+    /// * `Fetch` - The type of the query fetch.
+    ///
+    /// # Returns
+    /// A `QueryIter` iterator over the results of the query.
+    pub fn query<'a, Fetch: QueryFetch<'a>>(&'a self, index: AnyIndex) -> QueryIter<'a, Fetch> {
+        self.graph.query::<Fetch>(index)
+    }
+
+    /// Gets iterator over all node indices in the graph.
+    ///
+    /// # Returns
+    /// An iterator over the indices of all nodes.
+    pub fn indices(&self) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.graph.indices()
+    }
+}
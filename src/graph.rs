@@ -1,17 +1,75 @@
 use crate::{
+    command::GraphCommand,
+    prefab::{
+        GraphSnapshot, GraphSnapshotSeed, Prefab, PrefabError, deserialize_payload,
+        serialize_payload,
+    },
     query::{QueryFetch, QueryIter},
     relations::RelationsTable,
+    utils::type_name,
+};
+use intuicio_core::{
+    registry::Registry,
+    types::{Type, TypeQuery},
 };
 use intuicio_data::{
     lifetime::{ValueReadAccess, ValueWriteAccess},
     type_hash::TypeHash,
 };
-use intuicio_framework_arena::{AnyArena, AnyIndex, ArenaError};
+use intuicio_framework_arena::{AnyArena, AnyIndex, Arena, ArenaError};
+use intuicio_framework_serde::SerializationRegistry;
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    alloc::Layout,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque, hash_map::Entry},
     error::Error,
+    iter::{FusedIterator, Take},
 };
 
+/// Policy applied when new relation would exceed cardinality limits of its
+/// relation category.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CardinalityPolicy {
+    /// Oldest relations are removed to make room for the new one.
+    #[default]
+    EvictOldest,
+    /// New relation is not created.
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RelationCardinality {
+    max_out: Option<usize>,
+    max_in: Option<usize>,
+    policy: CardinalityPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationError {
+    CardinalityExceeded {
+        type_hash: TypeHash,
+        from: AnyIndex,
+        to: AnyIndex,
+    },
+}
+
+impl std::fmt::Display for RelationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CardinalityExceeded {
+                type_hash,
+                from,
+                to,
+            } => write!(
+                f,
+                "Relation {from} -> {to} exceeds cardinality of category: {type_hash:?}"
+            ),
+        }
+    }
+}
+
+impl Error for RelationError {}
+
 /// A graph data structure that allows for the storage of data in nodes and
 /// graph edges in form of relations.
 /// Each node can be of any type, and relations use types as categories.
@@ -20,6 +78,10 @@ use std::{
 pub struct Graph {
     pub(crate) nodes: AnyArena,
     pub(crate) relations: HashMap<TypeHash, RelationsTable>,
+    pub(crate) generation: u64,
+    pub(crate) tags: HashMap<TypeHash, HashSet<AnyIndex>>,
+    pub(crate) cardinalities: HashMap<TypeHash, RelationCardinality>,
+    pub(crate) recording: Option<Vec<GraphCommand>>,
 }
 
 impl Graph {
@@ -30,11 +92,122 @@ impl Graph {
     ///
     /// # Returns
     /// A new `Graph` instance with the specified arena capacity.
-    pub fn with_new_arena_capacity(self, capacity: usize) -> Self {
-        Self {
-            nodes: AnyArena::default().with_new_arena_capacity(capacity),
-            relations: Default::default(),
+    pub fn with_new_arena_capacity(mut self, capacity: usize) -> Self {
+        self.nodes.new_arena_capacity = capacity;
+        self
+    }
+
+    /// Pre-sizes relations table of specified relation category, so it can
+    /// hold at least specified number of relations between distinct nodes
+    /// without reallocation.
+    ///
+    /// # Arguments
+    /// * `capacity` - The capacity of the relations table.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// The `Graph` instance with pre-sized relations table.
+    pub fn with_relation_capacity<T>(mut self, capacity: usize) -> Self {
+        self.relations
+            .entry(TypeHash::of::<T>())
+            .or_default()
+            .reserve(capacity);
+        self
+    }
+
+    /// Returns the number of relations between distinct nodes that relations
+    /// table of specified relation category can hold without reallocation.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn relation_capacity<T>(&self) -> usize {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .map(|relations| relations.capacity())
+            .unwrap_or_default()
+    }
+
+    /// Reserves room for at least specified number of additional nodes of
+    /// specified type, so they can be inserted without reallocating arena.
+    /// Missing arena is created with that capacity. Existing arena that never
+    /// had nodes removed is rebuilt with exact capacity and its nodes moved
+    /// over, keeping their indices. Otherwise arena is grown by inserting and
+    /// removing default placeholders, which keeps indices valid but is slower.
+    ///
+    /// # Arguments
+    /// * `additional` - The number of additional nodes.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes.
+    pub fn reserve<T: Default>(&mut self, additional: usize) {
+        let type_hash = TypeHash::of::<T>();
+        let Some(arena) = self
+            .nodes
+            .arenas_mut()
+            .iter_mut()
+            .find(|arena| arena.type_hash() == type_hash)
+        else {
+            let capacity = std::mem::replace(&mut self.nodes.new_arena_capacity, additional);
+            self.nodes.ensure_arena::<T>();
+            self.nodes.new_arena_capacity = capacity;
+            return;
+        };
+        let len = arena.len();
+        if arena.capacity().saturating_sub(len) >= additional {
+            return;
+        }
+        let dense = arena
+            .indices()
+            .enumerate()
+            .all(|(id, index)| index.id() as usize == id && index.generation() == 0);
+        if dense {
+            let mut grown = Arena::new::<T>(len + additional);
+            for index in arena.indices() {
+                if let Ok(mut value) = arena.write::<T>(index) {
+                    let _ = grown.insert(std::mem::take(&mut *value));
+                }
+            }
+            *arena = grown;
+        } else {
+            let placeholders = (0..additional)
+                .filter_map(|_| arena.insert(T::default()).ok())
+                .collect::<Vec<_>>();
+            for index in placeholders.into_iter().rev() {
+                let _ = arena.remove(index);
+            }
+        }
+    }
+
+    /// Returns the number of nodes of the specified type that can be held
+    /// without reallocation.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes.
+    pub fn node_capacity<T>(&self) -> usize {
+        self.nodes
+            .arena::<T>()
+            .map(|arena| arena.capacity())
+            .unwrap_or_default()
+    }
+
+    /// Pre-creates empty arenas for specified types, so first insert of each
+    /// of them does not have to allocate new arena. Types that already have an
+    /// arena are left untouched.
+    ///
+    /// # Arguments
+    /// * `types` - The types to prepare arenas for.
+    /// * `capacity` - The capacity of each created arena.
+    pub fn prepare_types(&mut self, types: &[&Type], capacity: usize) {
+        let new_arena_capacity = std::mem::replace(&mut self.nodes.new_arena_capacity, capacity);
+        for type_ in types {
+            unsafe {
+                self.nodes
+                    .ensure_arena_raw(type_.type_hash(), *type_.layout(), type_.finalizer());
+            }
         }
+        self.nodes.new_arena_capacity = new_arena_capacity;
     }
 
     /// Inserts new node with provided data.
@@ -48,19 +221,255 @@ impl Graph {
         self.nodes.insert(value)
     }
 
+    /// Inserts new node with provided data and relates it with parent node
+    /// in both directions.
+    ///
+    /// # Arguments
+    /// * `parent` - The index of the parent node.
+    /// * `value` - The value to be inserted into the graph.
+    ///
+    /// # Type Parameters
+    /// * `I` - The type of the relation category for the new node towards
+    ///   parent node.
+    /// * `O` - The type of the relation category for the parent node towards
+    ///   new node.
+    ///
+    /// # Returns
+    /// The index of the newly inserted node.
+    pub fn insert_child<I, O, T>(&mut self, parent: AnyIndex, value: T) -> AnyIndex {
+        let index = self.insert(value);
+        self.relate_pair::<I, O>(parent, index);
+        index
+    }
+
+    /// Inserts new empty node, holding unit value. Empty nodes are useful as
+    /// structural-only nodes, carrying meaning only by their relations and tags.
+    ///
+    /// # Returns
+    /// The index of the newly inserted node.
+    pub fn insert_empty(&mut self) -> AnyIndex {
+        self.insert(())
+    }
+
+    /// Checks if the node at the specified index is an empty node.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to check.
+    ///
+    /// # Returns
+    /// `true` if the node exists and holds unit value, `false` otherwise.
+    pub fn is_empty_node(&self, index: AnyIndex) -> bool {
+        self.is::<()>(index) && self.contains(index)
+    }
+
     /// Removes node from the graph by its index.
     pub fn remove(&mut self, index: AnyIndex) -> Result<(), Box<dyn Error>> {
         self.nodes.remove(index)?;
         for relation in self.relations.values_mut() {
             relation.remove_all(index);
         }
+        for tagged in self.tags.values_mut() {
+            tagged.remove(&index);
+        }
+        Ok(())
+    }
+
+    /// Merges two nodes into one, by redirecting all incoming and outgoing
+    /// relations of removed node onto kept node in every relation category,
+    /// and then removing it. Node data is not merged, so nodes can be of
+    /// different types. Relations between merged nodes are dropped.
+    /// Redirected relations keep their payloads, unless kept node already has
+    /// the same relation, in which case its own payload is kept.
+    /// Cardinality limits are not enforced on redirected relations.
+    ///
+    /// # Arguments
+    /// * `keep` - The index of the node that receives relations.
+    /// * `remove` - The index of the node that gets removed.
+    ///
+    /// # Returns
+    /// A `Result` indicating success, or an error if either node does not
+    /// exist.
+    pub fn merge_nodes(&mut self, keep: AnyIndex, remove: AnyIndex) -> Result<(), Box<dyn Error>> {
+        if !self.nodes.contains(keep) {
+            return Err(ArenaError::IndexNotFound {
+                type_hash: keep.type_hash(),
+                index: keep.index(),
+            }
+            .into());
+        }
+        if keep == remove {
+            return Ok(());
+        }
+        self.nodes.remove(remove)?;
+        for relations in self.relations.values_mut() {
+            let outgoing = relations.outgoing(remove).collect::<Vec<_>>();
+            let incoming = relations.incoming(remove).collect::<Vec<_>>();
+            for to in outgoing {
+                if to == keep {
+                    relations.remove(remove, to);
+                } else {
+                    self.generation += 1;
+                    let new_to = if to == remove { keep } else { to };
+                    relations.move_relation(remove, to, keep, new_to, self.generation);
+                }
+            }
+            for from in incoming {
+                if from == keep {
+                    relations.remove(from, remove);
+                } else if from != remove {
+                    self.generation += 1;
+                    relations.move_relation(from, remove, from, keep, self.generation);
+                }
+            }
+        }
+        for tagged in self.tags.values_mut() {
+            tagged.remove(&remove);
+        }
         Ok(())
     }
 
+    /// Changes type of the node at the specified index, by replacing it with
+    /// new node and redirecting all its incoming and outgoing relations, in
+    /// every relation category, onto new node. Self relations are kept as
+    /// self relations of new node, and tags are moved to new node too.
+    /// New node is inserted before old one gets removed, and relations are
+    /// moved together with their payloads.
+    /// Cardinality limits are not enforced on redirected relations.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to change type of.
+    /// * `value` - The value of the new node.
+    ///
+    /// # Type Parameters
+    /// * `A` - The current type of the node.
+    /// * `B` - The new type of the node.
+    ///
+    /// # Returns
+    /// A `Result` containing the index of the new node or an error.
+    pub fn retype<A, B>(&mut self, index: AnyIndex, value: B) -> Result<AnyIndex, ArenaError> {
+        if !self.nodes.is::<A>(index)? {
+            return Err(ArenaError::InvalidAreaType {
+                type_hash: TypeHash::of::<A>(),
+            });
+        }
+        let result = self.nodes.insert(value);
+        for relations in self.relations.values_mut() {
+            let outgoing = relations.outgoing(index).collect::<Vec<_>>();
+            let incoming = relations.incoming(index).collect::<Vec<_>>();
+            for to in outgoing {
+                self.generation += 1;
+                let new_to = if to == index { result } else { to };
+                relations.move_relation(index, to, result, new_to, self.generation);
+            }
+            for from in incoming {
+                if from == index {
+                    continue;
+                }
+                self.generation += 1;
+                relations.move_relation(from, index, from, result, self.generation);
+            }
+        }
+        self.nodes.remove(index)?;
+        for tagged in self.tags.values_mut() {
+            if tagged.remove(&index) {
+                tagged.insert(result);
+            }
+        }
+        Ok(result)
+    }
+
     /// Removes all nodes and relations from the graph.
     pub fn clear(&mut self) {
         self.nodes.clear();
         self.relations.clear();
+        self.tags.clear();
+    }
+
+    /// Returns the total number of nodes in the graph.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks if the graph has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns read-only access to the underlying node storage.
+    pub fn nodes(&self) -> &AnyArena {
+        &self.nodes
+    }
+
+    /// Returns mutable access to the underlying node storage.
+    ///
+    /// # Safety
+    /// Relations and tags refer to nodes by their indices and are not updated
+    /// when nodes are changed through this storage. Caller must not remove or
+    /// move nodes that are still referenced by relations or tags, and must
+    /// keep arena types consistent with data stored in them.
+    pub unsafe fn nodes_mut(&mut self) -> &mut AnyArena {
+        &mut self.nodes
+    }
+
+    /// Gets iterator over type hashes of nodes present in the graph.
+    ///
+    /// # Returns
+    /// An iterator over type hashes of node types having at least one node.
+    pub fn node_type_hashes(&self) -> impl Iterator<Item = TypeHash> + '_ {
+        self.nodes
+            .arenas()
+            .iter()
+            .filter(|arena| !arena.is_empty())
+            .map(|arena| arena.type_hash())
+    }
+
+    /// Gets node indices grouped by node type, in deterministic order suitable
+    /// for stable presentation.
+    ///
+    /// # Arguments
+    /// * `registry` - The registry to use for type name lookups.
+    ///
+    /// # Returns
+    /// A list of type names and sorted node indices, ordered by type name.
+    pub fn nodes_grouped_sorted(&self, registry: &Registry) -> Vec<(String, Vec<AnyIndex>)> {
+        let mut result = self
+            .nodes
+            .arenas()
+            .iter()
+            .filter(|arena| !arena.is_empty())
+            .map(|arena| {
+                let mut indices = arena
+                    .indices()
+                    .map(|index| AnyIndex::new(index, arena.type_hash()))
+                    .collect::<Vec<_>>();
+                indices.sort();
+                (type_name(registry, arena.type_hash()), indices)
+            })
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+
+    /// Returns the number of nodes of the specified type.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes.
+    pub fn count_of<T>(&self) -> usize {
+        self.nodes
+            .arena::<T>()
+            .map(|arena| arena.len())
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of relations with the specified relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn relation_count<T>(&self) -> usize {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .map(|relations| relations.len())
+            .unwrap_or_default()
     }
 
     /// Checks if the graph contains a node with the specified index.
@@ -102,6 +511,30 @@ impl Graph {
         unsafe { self.nodes.read_ptr(index) }
     }
 
+    /// Returns read access to the node at the specified index as a raw pointer
+    /// together with memory layout of its actual type, so dynamic callers can
+    /// interpret it using type information from the registry.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to read.
+    ///
+    /// # Returns
+    /// A `Result` containing the raw pointer to the node and its layout or an
+    /// error.
+    ///
+    /// # Safety
+    /// The caller must ensure that the pointer is used safely and does not lead
+    /// to undefined behavior, since they get unrestricted memory block.
+    pub unsafe fn read_dynamic(&self, index: AnyIndex) -> Result<(*const u8, Layout), ArenaError> {
+        let arena = unsafe { self.nodes.arena_raw(index.type_hash()) }.ok_or(
+            ArenaError::InvalidAreaType {
+                type_hash: index.type_hash(),
+            },
+        )?;
+        let pointer = unsafe { arena.read_ptr(index.index())? };
+        Ok((pointer, *arena.item_layout()))
+    }
+
     /// Returns mutable write access to the node at the specified index.
     ///
     /// # Arguments
@@ -113,6 +546,20 @@ impl Graph {
         self.nodes.write(index)
     }
 
+    /// Replaces data of the node at the specified index, keeping its index and
+    /// relations intact.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to replace data of.
+    /// * `value` - The new value of the node.
+    ///
+    /// # Returns
+    /// A `Result` containing the old value of the node or an error.
+    pub fn replace<T>(&mut self, index: AnyIndex, value: T) -> Result<T, ArenaError> {
+        let mut access = self.nodes.write::<T>(index)?;
+        Ok(std::mem::replace(&mut *access, value))
+    }
+
     /// Returns mutable write access to the node at the specified index as a raw pointer.
     ///
     /// # Arguments
@@ -129,6 +576,8 @@ impl Graph {
     }
 
     /// Relates two nodes with specified relation category.
+    /// If relation would exceed cardinality limits of the category, oldest
+    /// relations get evicted or relation is skipped, depending on the policy.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
@@ -137,29 +586,23 @@ impl Graph {
     /// # Type Parameters
     /// * `T` - The type of the relation category.
     pub fn relate<T>(&mut self, from: AnyIndex, to: AnyIndex) {
-        self.relations
-            .entry(TypeHash::of::<T>())
-            .or_default()
-            .insert(from, to);
+        let _ = self.try_relate::<T>(from, to);
     }
 
-    /// Relates two nodes with specified relation category in both directions.
+    /// Relates two nodes with specified relation category.
+    /// If relation would exceed cardinality limits of the category, oldest
+    /// relations get evicted or relation is skipped, depending on the policy.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
     /// * `to` - The index of the target node.
-    ///
-    /// # Type Parameters
-    /// * `I` - The type of the relation category for the target node towards
-    ///   source node.
-    /// * `O` - The type of the relation category for the source node towards
-    ///   target node.
-    pub fn relate_pair<I, O>(&mut self, from: AnyIndex, to: AnyIndex) {
-        self.relate::<O>(from, to);
-        self.relate::<I>(to, from);
+    /// * `type_hash` - The type hash of the relation category.
+    pub fn relate_raw(&mut self, from: AnyIndex, to: AnyIndex, type_hash: TypeHash) {
+        let _ = self.try_relate_raw(from, to, type_hash);
     }
 
-    /// Unrelates two nodes with specified relation category.
+    /// Relates two nodes with specified relation category, reporting relations
+    /// rejected because of cardinality limits of the category.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
@@ -167,45 +610,144 @@ impl Graph {
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
-    pub fn unrelate<T>(&mut self, from: AnyIndex, to: AnyIndex) {
-        self.relations
-            .entry(TypeHash::of::<T>())
-            .or_default()
-            .remove(from, to);
+    ///
+    /// # Returns
+    /// A `Result` indicating success or cardinality error.
+    pub fn try_relate<T>(&mut self, from: AnyIndex, to: AnyIndex) -> Result<(), RelationError> {
+        self.try_relate_raw(from, to, TypeHash::of::<T>())
     }
 
-    /// Unrelates two nodes with specified relation category in both directions.
+    /// Relates two nodes with specified relation category, reporting relations
+    /// rejected because of cardinality limits of the category.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
     /// * `to` - The index of the target node.
+    /// * `type_hash` - The type hash of the relation category.
     ///
-    /// # Type Parameters
-    /// * `I` - The type of the relation category for the target node towards
-    ///   source node.
-    /// * `O` - The type of the relation category for the source node towards
-    ///   target node.
-    pub fn unrelate_pair<I, O>(&mut self, from: AnyIndex, to: AnyIndex) {
-        self.unrelate::<O>(from, to);
-        self.unrelate::<I>(to, from);
+    /// # Returns
+    /// A `Result` indicating success or cardinality error.
+    pub fn try_relate_raw(
+        &mut self,
+        from: AnyIndex,
+        to: AnyIndex,
+        type_hash: TypeHash,
+    ) -> Result<(), RelationError> {
+        let evicted = self.relation_evictions(from, to, type_hash, &[])?;
+        let relations = self.relations.entry(type_hash).or_default();
+        if relations.contains(from, to) {
+            return Ok(());
+        }
+        for (source, target) in evicted {
+            relations.remove(source, target);
+        }
+        self.generation += 1;
+        relations.insert_at(from, to, self.generation);
+        Ok(())
     }
 
-    /// Unrelates all nodes from the specified source node with the specified
-    /// relation category.
+    /// Computes relations to evict before relating two nodes, treating
+    /// relations listed in `removed` as already gone.
+    fn relation_evictions(
+        &self,
+        from: AnyIndex,
+        to: AnyIndex,
+        type_hash: TypeHash,
+        removed: &[(AnyIndex, AnyIndex)],
+    ) -> Result<Vec<(AnyIndex, AnyIndex)>, RelationError> {
+        let (Some(relations), Some(cardinality)) = (
+            self.relations.get(&type_hash),
+            self.cardinalities.get(&type_hash),
+        ) else {
+            return Ok(Default::default());
+        };
+        if relations.contains(from, to) {
+            return Ok(Default::default());
+        }
+        let outgoing = relations
+            .outgoing(from)
+            .filter(|target| !removed.contains(&(from, *target)))
+            .collect::<Vec<_>>();
+        let incoming = relations
+            .incoming(to)
+            .filter(|source| !removed.contains(&(*source, to)))
+            .collect::<Vec<_>>();
+        let out_count = outgoing.len();
+        let in_count = incoming.len();
+        let out_excess = cardinality
+            .max_out
+            .map(|max| (out_count + 1).saturating_sub(max))
+            .unwrap_or_default();
+        let in_excess = cardinality
+            .max_in
+            .map(|max| (in_count + 1).saturating_sub(max))
+            .unwrap_or_default();
+        if out_excess == 0 && in_excess == 0 {
+            return Ok(Default::default());
+        }
+        if cardinality.policy == CardinalityPolicy::Reject
+            || out_excess > out_count
+            || in_excess > in_count
+        {
+            return Err(RelationError::CardinalityExceeded {
+                type_hash,
+                from,
+                to,
+            });
+        }
+        let mut evicted = outgoing
+            .into_iter()
+            .map(|target| (relations.generation(from, target), target, from, target))
+            .collect::<Vec<_>>();
+        evicted.sort();
+        evicted.truncate(out_excess);
+        let mut incoming = incoming
+            .into_iter()
+            .map(|source| (relations.generation(source, to), source, source, to))
+            .collect::<Vec<_>>();
+        incoming.sort();
+        incoming.truncate(in_excess);
+        Ok(evicted
+            .into_iter()
+            .chain(incoming)
+            .map(|(_, _, source, target)| (source, target))
+            .collect())
+    }
+
+    /// Sets cardinality limits of specified relation category, enforced by
+    /// subsequent relations of that category. Existing relations are not
+    /// affected. Passing no limits removes them.
     ///
     /// # Arguments
-    /// * `from` - The index of the source node.
+    /// * `max_out` - The maximum number of outgoing relations of a node.
+    /// * `max_in` - The maximum number of incoming relations of a node.
+    /// * `policy` - The policy applied when limits would be exceeded.
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
-    pub fn unrelate_all<T>(&mut self, from: AnyIndex) {
-        self.relations
-            .entry(TypeHash::of::<T>())
-            .or_default()
-            .remove_all(from);
+    pub fn set_relation_cardinality<T>(
+        &mut self,
+        max_out: Option<usize>,
+        max_in: Option<usize>,
+        policy: CardinalityPolicy,
+    ) {
+        let type_hash = TypeHash::of::<T>();
+        if max_out.is_none() && max_in.is_none() {
+            self.cardinalities.remove(&type_hash);
+        } else {
+            self.cardinalities.insert(
+                type_hash,
+                RelationCardinality {
+                    max_out,
+                    max_in,
+                    policy,
+                },
+            );
+        }
     }
 
-    /// Checks if two nodes are related with the specified relation category.
+    /// Relates two nodes with specified relation category, only if both nodes
+    /// exist in the graph.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
@@ -213,346 +755,2755 @@ impl Graph {
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
-    pub fn are_related<T>(&self, from: AnyIndex, to: AnyIndex) -> bool {
-        self.relations
-            .get(&TypeHash::of::<T>())
-            .map(|relations| relations.contains(from, to))
-            .unwrap_or_default()
-    }
-
-    /// Gets iterator over all relations in the graph.
     ///
     /// # Returns
-    /// An iterator over tuples containing the type hash and the indices of the
-    /// related nodes.
-    pub fn relations(&self) -> impl Iterator<Item = (TypeHash, AnyIndex, AnyIndex)> + '_ {
-        self.relations.iter().flat_map(|(type_hash, relations)| {
-            relations
-                .iter_outgoing()
-                .map(|(from, to)| (*type_hash, from, to))
-        })
+    /// `true` if relation was created, `false` otherwise.
+    pub fn relate_if_present<T>(&mut self, from: AnyIndex, to: AnyIndex) -> bool {
+        if self.contains(from) && self.contains(to) {
+            self.relate::<T>(from, to);
+            true
+        } else {
+            false
+        }
     }
 
-    /// Gets iterator over all outgoing relations from the specified source node
-    /// with the specified relation category.
+    /// Relates two nodes with specified relation category in both directions.
+    /// Cardinality limits are applied to both directions together, see
+    /// `try_relate_pair`.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
     ///
     /// # Type Parameters
-    /// * `T` - The type of the relation category.
-    ///
-    /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn relations_outgoing<T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        self.relations_outgoing_raw(from, TypeHash::of::<T>())
+    /// * `I` - The type of the relation category for the target node towards
+    ///   source node.
+    /// * `O` - The type of the relation category for the source node towards
+    ///   target node.
+    pub fn relate_pair<I, O>(&mut self, from: AnyIndex, to: AnyIndex) {
+        let _ = self.try_relate_pair::<I, O>(from, to);
     }
 
-    /// Gets iterator over all outgoing relations in graph, no matter the source,
-    /// with specified relation category.
+    /// Relates two nodes with specified relation category in both directions,
+    /// applying cardinality limits to both directions together. If either
+    /// direction gets rejected, neither is created. Relations evicted to make
+    /// room get their opposite direction relation evicted too, so no half of
+    /// evicted pair is left behind.
     ///
-    /// # Type Parameters
-    /// * `T` - The type of the relation category.
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `I` - The type of the relation category for the target node towards
+    ///   source node.
+    /// * `O` - The type of the relation category for the source node towards
+    ///   target node.
     ///
     /// # Returns
-    /// An iterator over tuples containing the indices of the related nodes.
-    pub fn relations_outgoing_all<T>(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
-        self.relations_outgoing_all_raw(TypeHash::of::<T>())
+    /// A `Result` indicating success or cardinality error.
+    pub fn try_relate_pair<I, O>(
+        &mut self,
+        from: AnyIndex,
+        to: AnyIndex,
+    ) -> Result<(), RelationError> {
+        let outgoing = TypeHash::of::<O>();
+        let incoming = TypeHash::of::<I>();
+        let outgoing_evicted = self.relation_evictions(from, to, outgoing, &[])?;
+        let mut removed = outgoing_evicted
+            .iter()
+            .map(|(source, target)| (*target, *source))
+            .collect::<Vec<_>>();
+        if incoming == outgoing {
+            removed.extend(outgoing_evicted.iter().copied());
+        }
+        let incoming_evicted = self.relation_evictions(to, from, incoming, &removed)?;
+        for (source, target) in outgoing_evicted {
+            self.unrelate_raw(source, target, outgoing);
+            self.unrelate_raw(target, source, incoming);
+        }
+        for (source, target) in incoming_evicted {
+            self.unrelate_raw(source, target, incoming);
+            self.unrelate_raw(target, source, outgoing);
+        }
+        self.try_relate_raw(from, to, outgoing)?;
+        self.try_relate_raw(to, from, incoming)
     }
 
-    /// Gets iterator over all outgoing relations from the specified source node
-    /// with the specified relation category.
+    /// Relates two nodes with specified relation category, storing payload
+    /// data on that relation. Existing payload of that relation is replaced.
+    /// Payload is dropped if relation gets rejected by cardinality limits.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
-    /// * `type_hash` - The type hash of the relation category.
+    /// * `to` - The index of the target node.
+    /// * `payload` - The data stored on the relation.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category and its payload.
+    pub fn relate_with<T>(&mut self, from: AnyIndex, to: AnyIndex, payload: T) {
+        self.relate::<T>(from, to);
+        if let Some(relations) = self.relations.get_mut(&TypeHash::of::<T>())
+            && relations.contains(from, to)
+        {
+            relations.set_payload(from, to, payload);
+        }
+    }
+
+    /// Returns read access to payload data of relation between two nodes.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category and its payload.
     ///
     /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn relations_outgoing_raw(
+    /// The read access to the payload, or `None` if relation has no payload.
+    pub fn relation_data<T>(&self, from: AnyIndex, to: AnyIndex) -> Option<ValueReadAccess<'_, T>> {
+        self.relations.get(&TypeHash::of::<T>())?.payload(from, to)
+    }
+
+    /// Returns write access to payload data of relation between two nodes.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category and its payload.
+    ///
+    /// # Returns
+    /// The write access to the payload, or `None` if relation has no payload.
+    pub fn relation_data_mut<T>(
         &self,
         from: AnyIndex,
-        type_hash: TypeHash,
-    ) -> impl Iterator<Item = AnyIndex> + '_ {
+        to: AnyIndex,
+    ) -> Option<ValueWriteAccess<'_, T>> {
         self.relations
-            .get(&type_hash)
-            .into_iter()
-            .flat_map(move |relations| relations.outgoing(from))
+            .get(&TypeHash::of::<T>())?
+            .payload_mut(from, to)
     }
 
-    /// Gets iterator over all outgoing relations in graph, no matter the source,
-    /// with specified relation category.
+    /// Finds relations that break invariant maintained by `relate_pair`, where
+    /// only one of the two directions exists.
+    ///
+    /// # Type Parameters
+    /// * `I` - The type of the relation category for the target node towards
+    ///   source node.
+    /// * `O` - The type of the relation category for the source node towards
+    ///   target node.
+    ///
+    /// # Returns
+    /// A sorted list of `(from, to)` pairs, oriented as `from -O-> to`, that
+    /// are missing either `from -O-> to` or `to -I-> from` relation.
+    pub fn validate_pair<I, O>(&self) -> Vec<(AnyIndex, AnyIndex)> {
+        let mut result = self
+            .relations_outgoing_all::<O>()
+            .filter(|(from, to)| !self.are_related::<I>(*to, *from))
+            .chain(
+                self.relations_outgoing_all::<I>()
+                    .filter(|(to, from)| !self.are_related::<O>(*from, *to))
+                    .map(|(to, from)| (from, to)),
+            )
+            .collect::<Vec<_>>();
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Unrelates two nodes with specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn unrelate<T>(&mut self, from: AnyIndex, to: AnyIndex) {
+        self.unrelate_raw(from, to, TypeHash::of::<T>());
+    }
+
+    /// Unrelates two nodes with specified relation category.
     ///
     /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
     /// * `type_hash` - The type hash of the relation category.
+    pub fn unrelate_raw(&mut self, from: AnyIndex, to: AnyIndex, type_hash: TypeHash) {
+        if let Some(relations) = self.relations.get_mut(&type_hash) {
+            relations.remove(from, to);
+        }
+    }
+
+    /// Removes all incoming and outgoing relations of the specified node,
+    /// keeping node data and record of removed relations, so they can be
+    /// restored later with `reattach`. Relation payloads are not kept.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to detach.
     ///
     /// # Returns
-    /// An iterator over tuples containing the indices of the related nodes.
-    pub fn relations_outgoing_all_raw(
-        &self,
-        type_hash: TypeHash,
-    ) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
-        self.relations
-            .get(&type_hash)
+    /// The record of removed relations.
+    pub fn detach(&mut self, index: AnyIndex) -> DetachedRelations {
+        let mut result = DetachedRelations::default();
+        for (type_hash, relations) in &self.relations {
+            result
+                .outgoing
+                .extend(relations.outgoing(index).map(|to| (*type_hash, to)));
+            result
+                .incoming
+                .extend(relations.incoming(index).map(|from| (*type_hash, from)));
+        }
+        result.outgoing.sort();
+        result.incoming.sort();
+        for (type_hash, to) in &result.outgoing {
+            self.unrelate_raw(index, *to, *type_hash);
+        }
+        for (type_hash, from) in &result.incoming {
+            self.unrelate_raw(*from, index, *type_hash);
+        }
+        result
+    }
+
+    /// Restores relations removed by `detach`. Relations can be restored on
+    /// different node than the one they were detached from.
+    /// Cardinality limits are not enforced, so every relation is restored and
+    /// no other relation gets evicted to make room for it.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to reattach.
+    /// * `detached` - The record of relations to restore.
+    pub fn reattach(&mut self, index: AnyIndex, detached: DetachedRelations) {
+        let outgoing = detached
+            .outgoing
             .into_iter()
-            .flat_map(move |relations| relations.iter_outgoing())
+            .map(|(type_hash, to)| (type_hash, index, to));
+        let incoming = detached
+            .incoming
+            .into_iter()
+            .map(|(type_hash, from)| (type_hash, from, index));
+        for (type_hash, from, to) in outgoing.chain(incoming) {
+            let relations = self.relations.entry(type_hash).or_default();
+            if !relations.contains(from, to) {
+                self.generation += 1;
+                relations.insert_at(from, to, self.generation);
+            }
+        }
     }
 
-    /// Gets iterator over all outgoing relations from the specified source node
-    /// with any relation category.
+    /// Unrelates two nodes with specified relation category in both directions.
     ///
     /// # Arguments
     /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
     ///
-    /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn relations_outgoing_any(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        self.relations
-            .values()
-            .flat_map(move |relations| relations.outgoing(from))
+    /// # Type Parameters
+    /// * `I` - The type of the relation category for the target node towards
+    ///   source node.
+    /// * `O` - The type of the relation category for the source node towards
+    ///   target node.
+    pub fn unrelate_pair<I, O>(&mut self, from: AnyIndex, to: AnyIndex) {
+        self.unrelate::<O>(from, to);
+        self.unrelate::<I>(to, from);
     }
 
-    /// Gets iterator over incoming relations to the specified target node
-    /// with the specified relation category.
+    /// Unrelates all nodes from the specified source node with the specified
+    /// relation category.
     ///
     /// # Arguments
-    /// * `to` - The index of the target node.
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn unrelate_all<T>(&mut self, from: AnyIndex) {
+        if let Some(relations) = self.relations.get_mut(&TypeHash::of::<T>()) {
+            relations.remove_all(from);
+        }
+    }
+
+    /// Unrelates all nodes from the specified source nodes set to the specified
+    /// target nodes set with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `sources` - The indices of the source nodes.
+    /// * `targets` - The indices of the target nodes.
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
     ///
     /// # Returns
-    /// An iterator over the indices of the source nodes.
-    pub fn relations_incomming<T>(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        self.relations_incomming_raw(to, TypeHash::of::<T>())
+    /// The number of removed relations.
+    pub fn unrelate_between<T>(
+        &mut self,
+        sources: &HashSet<AnyIndex>,
+        targets: &HashSet<AnyIndex>,
+    ) -> usize {
+        let Some(relations) = self.relations.get_mut(&TypeHash::of::<T>()) else {
+            return 0;
+        };
+        let before = relations.len();
+        relations.retain(|from, to| !(sources.contains(&from) && targets.contains(&to)));
+        before - relations.len()
     }
 
-    /// Gets iterator over all incoming relations in graph, no matter the target,
-    /// with specified relation category.
+    /// Removes all relations of the specified relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn clear_relations<T>(&mut self) {
+        self.clear_relations_raw(TypeHash::of::<T>());
+    }
+
+    /// Removes all relations of the specified relation category.
+    ///
+    /// # Arguments
+    /// * `type_hash` - The type hash of the relation category.
+    pub fn clear_relations_raw(&mut self, type_hash: TypeHash) {
+        self.relations.remove(&type_hash);
+    }
+
+    /// Removes relation categories that have no relations left, releasing
+    /// memory after heavy relation churn.
+    pub fn shrink_relations(&mut self) {
+        self.relations.retain(|_, relations| !relations.is_empty());
+    }
+
+    /// Gets iterator over relation categories that have any relations.
+    ///
+    /// # Returns
+    /// An iterator over the type hashes of the relation categories.
+    pub fn relation_categories(&self) -> impl Iterator<Item = TypeHash> + '_ {
+        self.relations
+            .iter()
+            .filter(|(_, relations)| !relations.is_empty())
+            .map(|(type_hash, _)| *type_hash)
+    }
+
+    /// Checks if the graph has any relations of the specified relation category.
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
     ///
     /// # Returns
-    /// An iterator over tuples containing the indices of the related nodes.
-    pub fn relations_incomming_all<T>(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
-        self.relations_incomming_all_raw(TypeHash::of::<T>())
+    /// `true` if there is any relation of that category, `false` otherwise.
+    pub fn has_relation_category<T>(&self) -> bool {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .map(|relations| !relations.is_empty())
+            .unwrap_or_default()
     }
 
-    /// Gets iterator over incoming relations to the specified target node
-    /// with the specified relation category.
+    /// Swaps all relations of two relation categories with each other.
+    /// Relation payloads are typed by their relation category, so payloads
+    /// of both categories are removed.
+    /// Cardinality limits of either relation category are not enforced.
+    ///
+    /// # Type Parameters
+    /// * `A` - The type of the first relation category.
+    /// * `B` - The type of the second relation category.
+    pub fn swap_relation_categories<A, B>(&mut self) {
+        let a = TypeHash::of::<A>();
+        let b = TypeHash::of::<B>();
+        if a == b {
+            return;
+        }
+        let table_a = self.relations.remove(&a);
+        let table_b = self.relations.remove(&b);
+        if let Some(mut table) = table_b {
+            table.clear_payloads();
+            self.relations.insert(a, table);
+        }
+        if let Some(mut table) = table_a {
+            table.clear_payloads();
+            self.relations.insert(b, table);
+        }
+    }
+
+    /// Moves all relations of one relation category into another, merging
+    /// them with relations that already exist there.
+    /// Cardinality limits of the target relation category are not enforced.
+    /// Relation payloads are typed by their relation category, so payloads of
+    /// moved relations are removed, while payloads of relations that already
+    /// exist in target relation category are kept.
+    ///
+    /// # Type Parameters
+    /// * `Old` - The type of the relation category to move relations from.
+    /// * `New` - The type of the relation category to move relations to.
+    pub fn remap_relation<Old, New>(&mut self) {
+        let old = TypeHash::of::<Old>();
+        let new = TypeHash::of::<New>();
+        if old == new {
+            return;
+        }
+        let Some(table) = self.relations.remove(&old) else {
+            return;
+        };
+        match self.relations.get_mut(&new) {
+            Some(target) => {
+                for (from, to) in table.iter_outgoing() {
+                    target.insert_at(from, to, table.generation(from, to).unwrap_or_default());
+                }
+            }
+            None => {
+                let mut table = table;
+                table.clear_payloads();
+                self.relations.insert(new, table);
+            }
+        }
+    }
+
+    /// Keeps only relations of specified relation category for which the
+    /// predicate returns true, removing the rest.
     ///
     /// # Arguments
+    /// * `predicate` - The function deciding if relation between source and
+    ///   target nodes should be kept.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn retain_relations<T>(&mut self, predicate: impl Fn(AnyIndex, AnyIndex) -> bool) {
+        if let Some(relations) = self.relations.get_mut(&TypeHash::of::<T>()) {
+            relations.retain(predicate);
+        }
+    }
+
+    /// Checks if two nodes are related with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
     /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn are_related<T>(&self, from: AnyIndex, to: AnyIndex) -> bool {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .map(|relations| relations.contains(from, to))
+            .unwrap_or_default()
+    }
+
+    /// Checks if the specified node is target of any relation in any relation
+    /// category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    pub fn is_referenced(&self, index: AnyIndex) -> bool {
+        self.relations_incoming_any(index).next().is_some()
+    }
+
+    /// Checks if the specified node is source of any relation in any relation
+    /// category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    pub fn has_references(&self, index: AnyIndex) -> bool {
+        self.relations_outgoing_any(index).next().is_some()
+    }
+
+    /// Returns the number of outgoing relations of the specified node with the
+    /// specified relation category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn out_degree<T>(&self, index: AnyIndex) -> usize {
+        self.out_degree_raw(index, TypeHash::of::<T>())
+    }
+
+    /// Returns the number of outgoing relations of the specified node with the
+    /// specified relation category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
     /// * `type_hash` - The type hash of the relation category.
+    pub fn out_degree_raw(&self, index: AnyIndex, type_hash: TypeHash) -> usize {
+        self.relations
+            .get(&type_hash)
+            .map(|relations| relations.out_degree(index))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of outgoing relations of the specified node with any
+    /// relation category.
     ///
-    /// # Returns
-    /// An iterator over the indices of the source nodes.
-    pub fn relations_incomming_raw(
-        &self,
-        to: AnyIndex,
-        type_hash: TypeHash,
-    ) -> impl Iterator<Item = AnyIndex> + '_ {
+    /// # Arguments
+    /// * `index` - The index of the node.
+    pub fn out_degree_any(&self, index: AnyIndex) -> usize {
+        self.relations
+            .values()
+            .map(|relations| relations.out_degree(index))
+            .sum()
+    }
+
+    /// Returns the number of incoming relations of the specified node with the
+    /// specified relation category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn in_degree<T>(&self, index: AnyIndex) -> usize {
+        self.in_degree_raw(index, TypeHash::of::<T>())
+    }
+
+    /// Returns the number of incoming relations of the specified node with the
+    /// specified relation category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    /// * `type_hash` - The type hash of the relation category.
+    pub fn in_degree_raw(&self, index: AnyIndex, type_hash: TypeHash) -> usize {
         self.relations
             .get(&type_hash)
-            .into_iter()
-            .flat_map(move |relations| relations.incoming(to))
+            .map(|relations| relations.in_degree(index))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of incoming relations of the specified node with any
+    /// relation category.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    pub fn in_degree_any(&self, index: AnyIndex) -> usize {
+        self.relations
+            .values()
+            .map(|relations| relations.in_degree(index))
+            .sum()
+    }
+
+    /// Adds tag to the node at the specified index.
+    /// Tags are lightweight typed markers that do not need separate nodes and
+    /// relations. They are transient and not stored in prefabs.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to tag.
+    ///
+    /// # Type Parameters
+    /// * `Tag` - The type of the tag.
+    pub fn add_tag<Tag>(&mut self, index: AnyIndex) {
+        self.tags
+            .entry(TypeHash::of::<Tag>())
+            .or_default()
+            .insert(index);
+    }
+
+    /// Removes tag from the node at the specified index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to untag.
+    ///
+    /// # Type Parameters
+    /// * `Tag` - The type of the tag.
+    pub fn remove_tag<Tag>(&mut self, index: AnyIndex) {
+        if let Some(tagged) = self.tags.get_mut(&TypeHash::of::<Tag>()) {
+            tagged.remove(&index);
+        }
+    }
+
+    /// Checks if the node at the specified index has specified tag.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to check.
+    ///
+    /// # Type Parameters
+    /// * `Tag` - The type of the tag.
+    pub fn has_tag<Tag>(&self, index: AnyIndex) -> bool {
+        self.tags
+            .get(&TypeHash::of::<Tag>())
+            .map(|tagged| tagged.contains(&index))
+            .unwrap_or_default()
+    }
+
+    /// Gets iterator over all nodes with specified tag.
+    ///
+    /// # Type Parameters
+    /// * `Tag` - The type of the tag.
+    ///
+    /// # Returns
+    /// An iterator over the indices of tagged nodes.
+    pub fn tagged<Tag>(&self) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.tags
+            .get(&TypeHash::of::<Tag>())
+            .into_iter()
+            .flat_map(|tagged| tagged.iter().copied())
+    }
+
+    /// Gets read-only view of the relations table for specified relation
+    /// category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A view of the relations table, or `None` if the category is absent.
+    pub fn relation_table_view<T>(&self) -> Option<RelationTableView<'_>> {
+        self.relations
+            .get(&TypeHash::of::<T>())
+            .map(|table| RelationTableView { table })
+    }
+
+    /// Gets view of relations of the specified node, which resolves its
+    /// relations in every category once and reuses them for every lookup.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    ///
+    /// # Returns
+    /// A view of the node relations.
+    pub fn node_view(&self, index: AnyIndex) -> NodeView<'_> {
+        let mut outgoing = Vec::new();
+        let mut incoming = Vec::new();
+        for (type_hash, table) in &self.relations {
+            if let Some(set) = table.outgoing_set(index) {
+                outgoing.push((*type_hash, set));
+            }
+            if let Some(set) = table.incoming_set(index) {
+                incoming.push((*type_hash, set));
+            }
+        }
+        NodeView {
+            index,
+            outgoing,
+            incoming,
+        }
+    }
+
+    /// Gets checker of relations with specified relation category, which
+    /// resolves relations table once and reuses it for every check.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn relation_checker<T>(&self) -> RelationChecker<'_> {
+        RelationChecker {
+            table: self.relations.get(&TypeHash::of::<T>()),
+        }
+    }
+
+    /// Gets current relations generation.
+    /// Generation is increased with every relation made, so it can be used to
+    /// mark a point in time for `Graph::relations_since`.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Gets iterator over all relations made after specified generation.
+    ///
+    /// # Arguments
+    /// * `generation` - The generation after which relations were made.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the type hash and the indices of the
+    /// related nodes.
+    pub fn relations_since(
+        &self,
+        generation: u64,
+    ) -> impl Iterator<Item = (TypeHash, AnyIndex, AnyIndex)> + '_ {
+        self.relations
+            .iter()
+            .flat_map(move |(type_hash, relations)| {
+                relations
+                    .iter_since(generation)
+                    .map(|(from, to)| (*type_hash, from, to))
+            })
+    }
+
+    /// Gets iterator over all relations in the graph.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the type hash and the indices of the
+    /// related nodes.
+    pub fn relations(&self) -> impl Iterator<Item = (TypeHash, AnyIndex, AnyIndex)> + '_ {
+        self.relations.iter().flat_map(|(type_hash, relations)| {
+            relations
+                .iter_outgoing()
+                .map(|(from, to)| (*type_hash, from, to))
+        })
+    }
+
+    /// Gets all relations in the graph in deterministic order.
+    ///
+    /// # Returns
+    /// A list of tuples containing the type hash and the indices of the
+    /// related nodes, sorted by type hash, source and target.
+    pub fn relations_canonical(&self) -> Vec<(TypeHash, AnyIndex, AnyIndex)> {
+        let mut result = self.relations().collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    /// Finds pairs of nodes related with more than one relation category.
+    ///
+    /// # Returns
+    /// An iterator over source and target indices with sorted list of relation
+    /// categories relating them.
+    pub fn multi_edges(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex, Vec<TypeHash>)> {
+        let mut pairs = HashMap::<(AnyIndex, AnyIndex), Vec<TypeHash>>::default();
+        for (type_hash, from, to) in self.relations() {
+            pairs.entry((from, to)).or_default().push(type_hash);
+        }
+        pairs
+            .into_iter()
+            .filter(|(_, categories)| categories.len() > 1)
+            .map(|((from, to), mut categories)| {
+                categories.sort();
+                (from, to, categories)
+            })
+    }
+
+    /// Computes difference between relations of this graph and other graph,
+    /// assuming node indices in both graphs refer to the same nodes.
+    ///
+    /// # Arguments
+    /// * `other` - The graph to compare against.
+    ///
+    /// # Returns
+    /// A pair of sorted lists of relations (category, source, target): ones
+    /// that exist only in this graph and ones that exist only in other graph.
+    #[allow(clippy::type_complexity)]
+    pub fn relation_difference(
+        &self,
+        other: &Graph,
+    ) -> (
+        Vec<(TypeHash, AnyIndex, AnyIndex)>,
+        Vec<(TypeHash, AnyIndex, AnyIndex)>,
+    ) {
+        fn only_in(a: &Graph, b: &Graph) -> Vec<(TypeHash, AnyIndex, AnyIndex)> {
+            let mut result = a
+                .relations()
+                .filter(|(type_hash, from, to)| {
+                    !b.relations
+                        .get(type_hash)
+                        .map(|relations| relations.contains(*from, *to))
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>();
+            result.sort();
+            result
+        }
+
+        (only_in(self, other), only_in(other, self))
+    }
+
+    /// Computes outgoing relations count of every node, per relation category.
+    /// Only nodes and categories with at least one outgoing relation are
+    /// included.
+    ///
+    /// # Returns
+    /// A map from node index to map of relation category type hash and number
+    /// of outgoing relations.
+    pub fn outgoing_degree_matrix(&self) -> HashMap<AnyIndex, HashMap<TypeHash, usize>> {
+        let mut result = HashMap::<AnyIndex, HashMap<TypeHash, usize>>::default();
+        for (type_hash, relations) in &self.relations {
+            for (from, count) in relations.outgoing_degrees() {
+                result.entry(from).or_default().insert(*type_hash, count);
+            }
+        }
+        result
+    }
+
+    /// Gets iterator over all outgoing relations from the specified source node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_outgoing<T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_outgoing_raw(from, TypeHash::of::<T>())
+    }
+
+    /// Gets iterator over all outgoing relations in graph, no matter the source,
+    /// with specified relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the indices of the related nodes.
+    pub fn relations_outgoing_all<T>(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.relations_outgoing_all_raw(TypeHash::of::<T>())
+    }
+
+    /// Calls the callback for every relation in graph, no matter the source,
+    /// with specified relation category. This is push-style counterpart of
+    /// `relations_outgoing_all` that walks relations without building iterator
+    /// adapters.
+    ///
+    /// # Arguments
+    /// * `f` - The callback receiving indices of source and target nodes.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    pub fn for_each_edge<T>(&self, f: impl FnMut(AnyIndex, AnyIndex)) {
+        if let Some(relations) = self.relations.get(&TypeHash::of::<T>()) {
+            relations.for_each_outgoing(f);
+        }
+    }
+
+    /// Gets iterator over all outgoing relations from the specified source node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `type_hash` - The type hash of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_outgoing_raw(
+        &self,
+        from: AnyIndex,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations
+            .get(&type_hash)
+            .into_iter()
+            .flat_map(move |relations| relations.outgoing(from))
+    }
+
+    /// Gets iterator over all outgoing relations in graph, no matter the source,
+    /// with specified relation category.
+    ///
+    /// # Arguments
+    /// * `type_hash` - The type hash of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the indices of the related nodes.
+    pub fn relations_outgoing_all_raw(
+        &self,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.relations
+            .get(&type_hash)
+            .into_iter()
+            .flat_map(move |relations| relations.iter_outgoing())
+    }
+
+    /// Gets iterator over all outgoing relations from the specified source node
+    /// with any relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_outgoing_any(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations
+            .values()
+            .flat_map(move |relations| relations.outgoing(from))
+    }
+
+    /// Gets iterator over incoming relations to the specified target node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the source nodes.
+    pub fn relations_incoming<T>(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_incoming_raw(to, TypeHash::of::<T>())
+    }
+
+    /// Gets iterator over all incoming relations in graph, no matter the target,
+    /// with specified relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the indices of the related nodes.
+    pub fn relations_incoming_all<T>(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.relations_incoming_all_raw(TypeHash::of::<T>())
+    }
+
+    /// Gets iterator over incoming relations to the specified target node
+    /// with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `to` - The index of the target node.
+    /// * `type_hash` - The type hash of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the source nodes.
+    pub fn relations_incoming_raw(
+        &self,
+        to: AnyIndex,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations
+            .get(&type_hash)
+            .into_iter()
+            .flat_map(move |relations| relations.incoming(to))
+    }
+
+    /// Gets iterator over all incoming relations in graph, no matter the target,
+    /// with specified relation category.
+    ///
+    /// # Arguments
+    /// * `type_hash` - The type hash of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the indices of the related nodes.
+    pub fn relations_incoming_all_raw(
+        &self,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.relations
+            .get(&type_hash)
+            .into_iter()
+            .flat_map(move |relations| relations.iter_incoming())
+    }
+
+    /// Gets iterator over incoming relations to the specified target node
+    /// with any relation category.
+    ///
+    /// # Arguments
+    /// * `to` - The index of the target node.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the source nodes.
+    pub fn relations_incoming_any(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations
+            .values()
+            .flat_map(move |relations| relations.incoming(to))
+    }
+
+    /// Misspelled alias of `Graph::relations_incoming`.
+    #[deprecated(note = "Use `Graph::relations_incoming` instead")]
+    pub fn relations_incomming<T>(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_incoming::<T>(to)
+    }
+
+    /// Misspelled alias of `Graph::relations_incoming_all`.
+    #[deprecated(note = "Use `Graph::relations_incoming_all` instead")]
+    pub fn relations_incomming_all<T>(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.relations_incoming_all::<T>()
+    }
+
+    /// Misspelled alias of `Graph::relations_incoming_raw`.
+    #[deprecated(note = "Use `Graph::relations_incoming_raw` instead")]
+    pub fn relations_incomming_raw(
+        &self,
+        to: AnyIndex,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_incoming_raw(to, type_hash)
+    }
+
+    /// Misspelled alias of `Graph::relations_incoming_all_raw`.
+    #[deprecated(note = "Use `Graph::relations_incoming_all_raw` instead")]
+    pub fn relations_incomming_all_raw(
+        &self,
+        type_hash: TypeHash,
+    ) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.relations_incoming_all_raw(type_hash)
+    }
+
+    /// Misspelled alias of `Graph::relations_incoming_any`.
+    #[deprecated(note = "Use `Graph::relations_incoming_any` instead")]
+    pub fn relations_incomming_any(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_incoming_any(to)
+    }
+
+    /// Gets traverse iterator over all relations from the specified source node
+    /// with the specified relation category.
+    /// Nodes are visited in breadth-first order, starting with the source node.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse<T>(
+        &self,
+        from: AnyIndex,
+    ) -> impl FusedIterator<Item = AnyIndex> + '_ {
+        GraphTraverseIter::new::<T>(self, from)
+    }
+
+    /// Gets traverse iterator over all relations from the specified source node
+    /// with the specified relation category.
+    /// Nodes are visited in breadth-first order, starting with the source node.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `type_hash` - The type hash of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse_raw(
+        &self,
+        from: AnyIndex,
+        type_hash: TypeHash,
+    ) -> impl FusedIterator<Item = AnyIndex> + '_ {
+        GraphTraverseIter::new_raw(self, from, type_hash)
+    }
+
+    /// Gets depth-first traverse iterator over all relations from the specified
+    /// source node with the specified relation category.
+    /// Nodes are visited in pre-order, starting with the source node.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse_dfs<T>(
+        &self,
+        from: AnyIndex,
+    ) -> impl FusedIterator<Item = AnyIndex> + '_ {
+        GraphTraverseDfsIter {
+            graph: self,
+            stack: vec![from],
+            visited: Default::default(),
+            type_hash: TypeHash::of::<T>(),
+        }
+    }
+
+    /// Gets traverse iterator over relations from the specified source node
+    /// with the specified relation category, limited to nodes at most
+    /// `max_depth` relations away from the source node.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `max_depth` - The maximum number of relations to follow.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse_depth<T>(
+        &self,
+        from: AnyIndex,
+        max_depth: usize,
+    ) -> impl FusedIterator<Item = AnyIndex> + '_ {
+        self.relations_traverse_depth_raw(from, TypeHash::of::<T>(), max_depth)
+    }
+
+    /// Gets traverse iterator over relations from the specified source node
+    /// with the specified relation category, limited to nodes at most
+    /// `max_depth` relations away from the source node.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `type_hash` - The type hash of the relation category.
+    /// * `max_depth` - The maximum number of relations to follow.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse_depth_raw(
+        &self,
+        from: AnyIndex,
+        type_hash: TypeHash,
+        max_depth: usize,
+    ) -> impl FusedIterator<Item = AnyIndex> + '_ {
+        GraphTraverseDepthIter {
+            graph: self,
+            stack: [(from, 0)].into(),
+            visited: Default::default(),
+            type_hash,
+            max_depth,
+        }
+        .map(|(_, index)| index)
+    }
+
+    /// Gets breadth-first traverse iterator over all relations from the
+    /// specified source node with the specified relation category, reporting
+    /// depth of each node. Source node has depth 0, and nodes reachable in
+    /// multiple ways report the shortest depth.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over depths and indices of the target nodes.
+    pub fn relations_traverse_with_depth<T>(
+        &self,
+        from: AnyIndex,
+    ) -> impl FusedIterator<Item = (usize, AnyIndex)> + '_ {
+        GraphTraverseDepthIter {
+            graph: self,
+            stack: [(from, 0)].into(),
+            visited: Default::default(),
+            type_hash: TypeHash::of::<T>(),
+            max_depth: usize::MAX,
+        }
+    }
+
+    /// Finds nodes whose shortest distance from the specified source node,
+    /// following relations of the specified relation category, equals depth.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `depth` - The number of relations between source and found nodes.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A list of node indices at the specified depth.
+    pub fn nodes_at_depth<T>(&self, from: AnyIndex, depth: usize) -> Vec<AnyIndex> {
+        let mut visited = HashSet::from([from]);
+        let mut level = vec![from];
+        for _ in 0..depth {
+            level = level
+                .into_iter()
+                .flat_map(|index| self.relations_outgoing::<T>(index))
+                .filter(|index| visited.insert(*index))
+                .collect();
+            if level.is_empty() {
+                break;
+            }
+        }
+        level
+    }
+
+    /// Traverses all relations from the specified source node with the
+    /// specified relation category in depth-first order, failing as soon as
+    /// relation pointing back to a node on current path is found.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing visited nodes in order, or a cycle path.
+    pub fn try_traverse<T>(&self, from: AnyIndex) -> Result<Vec<AnyIndex>, Vec<AnyIndex>> {
+        let mut visited = HashSet::from([from]);
+        let mut result = vec![from];
+        let mut path = vec![from];
+        let mut stack = vec![self.relations_outgoing::<T>(from).collect::<Vec<_>>()];
+        while let Some(targets) = stack.last_mut() {
+            let Some(target) = targets.pop() else {
+                stack.pop();
+                path.pop();
+                continue;
+            };
+            if let Some(position) = path.iter().position(|index| *index == target) {
+                return Err(path[position..].to_vec());
+            }
+            if visited.insert(target) {
+                result.push(target);
+                path.push(target);
+                stack.push(self.relations_outgoing::<T>(target).collect());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Collects all nodes reachable from the specified source node with the
+    /// specified relation category in topological order, so every node comes
+    /// before nodes it relates to.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing nodes in topological order, or nodes in traversal
+    /// order if reachable relations contain a cycle.
+    pub fn relations_traverse_topological<T>(
+        &self,
+        from: AnyIndex,
+    ) -> Result<Vec<AnyIndex>, Vec<AnyIndex>> {
+        let reachable = self.relations_traverse::<T>(from).collect::<Vec<_>>();
+        let mut degrees = reachable
+            .iter()
+            .map(|index| (*index, 0usize))
+            .collect::<HashMap<_, _>>();
+        for source in &reachable {
+            for target in self.relations_outgoing::<T>(*source) {
+                if let Some(degree) = degrees.get_mut(&target) {
+                    *degree += 1;
+                }
+            }
+        }
+        let mut queue = reachable
+            .iter()
+            .filter(|index| degrees[*index] == 0)
+            .copied()
+            .collect::<VecDeque<_>>();
+        let mut result = Vec::with_capacity(reachable.len());
+        while let Some(source) = queue.pop_front() {
+            result.push(source);
+            for target in self.relations_outgoing::<T>(source) {
+                if let Some(degree) = degrees.get_mut(&target) {
+                    *degree -= 1;
+                    if *degree == 0 {
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+        if result.len() == reachable.len() {
+            Ok(result)
+        } else {
+            Err(reachable)
+        }
+    }
+
+    /// Gets traverse iterator over all relations from the specified source node
+    /// with any relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse_any(
+        &self,
+        from: AnyIndex,
+    ) -> impl FusedIterator<Item = AnyIndex> + '_ {
+        GraphTraverseAnyIter::new(self, from)
+    }
+
+    /// Gets traverse iterator over all relations from the specified source node
+    /// with the specified relation category, where nodes are expanded in
+    /// ascending order of their key (best-first traversal).
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `key` - The function producing expansion priority key for a node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    /// * `K` - The type of the priority key.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn relations_traverse_by_key<T, K: Ord>(
+        &self,
+        from: AnyIndex,
+        key: impl Fn(AnyIndex) -> K,
+    ) -> impl FusedIterator<Item = AnyIndex> {
+        GraphTraverseByKeyIter::new(self, from, TypeHash::of::<T>(), key)
+    }
+
+    /// Collects types of all nodes being sources of relations with specified
+    /// relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A set of type hashes of the source nodes.
+    pub fn source_types<T>(&self) -> HashSet<TypeHash> {
+        self.relations_outgoing_all::<T>()
+            .map(|(from, _)| from.type_hash())
+            .collect()
+    }
+
+    /// Collects types of all nodes being targets of relations with specified
+    /// relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A set of type hashes of the target nodes.
+    pub fn target_types<T>(&self) -> HashSet<TypeHash> {
+        self.relations_outgoing_all::<T>()
+            .map(|(_, to)| to.type_hash())
+            .collect()
+    }
+
+    /// Finds all nodes of the specified type that are related to the specified
+    /// source node with the specified relation category.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    ///
+    /// # Type Parameters
+    /// * `R` - The type of the relation category.
+    /// * `T` - The type of the target nodes.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the target nodes.
+    pub fn find<R, T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.relations_outgoing::<R>(from)
+            .filter(|index| self.is::<T>(*index))
+    }
+
+    /// Picks random target node related to the specified source node, with
+    /// probability proportional to its weight. Nodes with zero, negative or
+    /// non-finite weights are never picked. Candidates are ordered by index,
+    /// so the same random number generator state always picks the same node.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `weight` - The function computing weight of target node.
+    /// * `rng` - The random number generator.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// The index of picked target node, or `None` if no node has positive weight.
+    #[cfg(feature = "rand")]
+    pub fn weighted_random_neighbor<T>(
+        &self,
+        from: AnyIndex,
+        weight: impl Fn(AnyIndex) -> f64,
+        rng: &mut impl rand::Rng,
+    ) -> Option<AnyIndex> {
+        let mut candidates = self
+            .relations_outgoing::<T>(from)
+            .map(|index| (index, weight(index)))
+            .filter(|(_, weight)| weight.is_finite() && *weight > 0.0)
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(index, _)| *index);
+        let total = candidates.iter().map(|(_, weight)| *weight).sum::<f64>();
+        if !total.is_finite() || total <= 0.0 {
+            return None;
+        }
+        let mut pick = rng.random_range(0.0..total);
+        for (index, weight) in &candidates {
+            if pick < *weight {
+                return Some(*index);
+            }
+            pick -= weight;
+        }
+        candidates.last().map(|(index, _)| *index)
+    }
+
+    /// Finds root of the hierarchy containing the specified node, by following
+    /// incoming relations of the specified relation category up to the topmost
+    /// ancestor. When node has multiple parents, only the one with the lowest
+    /// index is followed, so result does not depend on relation storage order.
+    /// Cycles are detected, in which case the last node visited before
+    /// reaching already visited node is returned.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to find root of.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// The index of the root node, or `index` if node has no parent.
+    pub fn root_of<T>(&self, index: AnyIndex) -> AnyIndex {
+        let mut visited = HashSet::from([index]);
+        let mut current = index;
+        while let Some(parent) = self.relations_incoming::<T>(current).min() {
+            if !visited.insert(parent) {
+                break;
+            }
+            current = parent;
+        }
+        current
+    }
+
+    /// Finds target nodes of the specified type related to the specified
+    /// source node, sorted ascending by key computed from their data.
+    /// Nodes are collected and sorted eagerly; nodes with equal keys are
+    /// ordered by their index, so result is deterministic.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `key` - The function computing sort key from node data.
+    ///
+    /// # Type Parameters
+    /// * `R` - The type of the relation category.
+    /// * `T` - The type of the target nodes.
+    /// * `K` - The type of the sort key.
+    ///
+    /// # Returns
+    /// A list of sorted indices of the target nodes.
+    pub fn query_related_sorted<R, T, K: Ord>(
+        &self,
+        from: AnyIndex,
+        key: impl Fn(&T) -> K,
+    ) -> Vec<AnyIndex> {
+        let mut result = self
+            .find::<R, T>(from)
+            .filter_map(|index| Some((key(&*self.read::<T>(index).ok()?), index)))
+            .collect::<Vec<_>>();
+        result.sort();
+        result.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Performs query on the graph using the specified index.
+    pub fn query<'a, Fetch: QueryFetch<'a>>(&'a self, index: AnyIndex) -> QueryIter<'a, Fetch> {
+        QueryIter::new(self, index)
+    }
+
+    /// Performs query on the graph using the specified index, returning only
+    /// first fetched value.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to start query from.
+    ///
+    /// # Returns
+    /// The first fetched value, or `None` if query produced no values.
+    pub fn query_one<'a, Fetch: QueryFetch<'a>>(&'a self, index: AnyIndex) -> Option<Fetch::Value> {
+        self.query::<Fetch>(index).next()
+    }
+
+    /// Counts values produced by query on the graph using the specified index.
+    /// Each fetched value is dropped right away, so write accesses are never
+    /// held at the same time.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to start query from.
+    ///
+    /// # Returns
+    /// The number of fetched values.
+    pub fn query_count<'a, Fetch: QueryFetch<'a>>(&'a self, index: AnyIndex) -> usize {
+        self.query::<Fetch>(index).count()
+    }
+
+    /// Performs query on the graph using the specified index, stopping after
+    /// specified number of fetched values.
+    /// This is equivalent to calling `Iterator::take` on `Graph::query` result,
+    /// useful when result cap is known only at runtime.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node to start query from.
+    /// * `max` - The maximum number of fetched values.
+    pub fn query_limited<'a, Fetch: QueryFetch<'a>>(
+        &'a self,
+        index: AnyIndex,
+        max: usize,
+    ) -> Take<QueryIter<'a, Fetch>> {
+        self.query::<Fetch>(index).take(max)
+    }
+
+    /// Performs query on the graph starting from every node of specified type.
+    /// Queries are performed lazily one root after another, so accesses
+    /// fetched from one root can be dropped before next root gets queried.
+    ///
+    /// # Type Parameters
+    /// * `Root` - The type of the root nodes.
+    /// * `Fetch` - The query fetch to perform for each root node.
+    ///
+    /// # Returns
+    /// An iterator over fetched values.
+    pub fn query_all<'a, Root, Fetch: QueryFetch<'a>>(
+        &'a self,
+    ) -> impl Iterator<Item = Fetch::Value> {
+        self.query_all_with_root::<Root, Fetch>()
+            .map(|(_, value)| value)
+    }
+
+    /// Performs query on the graph starting from every node of specified type,
+    /// pairing each fetched value with the root node it originated from.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the root nodes.
+    /// * `Fetch` - The query fetch to perform for each root node.
+    ///
+    /// # Returns
+    /// An iterator over tuples containing the root node index and fetched value.
+    pub fn query_all_with_root<'a, T, Fetch: QueryFetch<'a>>(
+        &'a self,
+    ) -> impl Iterator<Item = (AnyIndex, Fetch::Value)> {
+        self.nodes
+            .arena::<T>()
+            .into_iter()
+            .flat_map(|arena| {
+                arena
+                    .indices()
+                    .map(|index| AnyIndex::new(index, arena.type_hash()))
+            })
+            .flat_map(|root| self.query::<Fetch>(root).map(move |value| (root, value)))
+    }
+
+    /// Gets iterator over all nodes of specified type in the graph.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes to iterate over.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the nodes and their read access.
+    pub fn iter<'a, T: 'a>(&'a self) -> impl Iterator<Item = (AnyIndex, ValueReadAccess<'a, T>)> {
+        self.nodes.arena::<T>().into_iter().flat_map(|arena| {
+            arena
+                .indices()
+                .map(|index| AnyIndex::new(index, arena.type_hash()))
+                .zip(arena.iter::<T>())
+        })
+    }
+
+    /// Collects owned copies of all nodes of specified type in the graph.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes to collect.
+    ///
+    /// # Returns
+    /// A list of indices of the nodes and their cloned values.
+    pub fn collect_type<T: Clone>(&self) -> Vec<(AnyIndex, T)> {
+        self.iter::<T>()
+            .map(|(index, value)| (index, value.clone()))
+            .collect()
+    }
+
+    /// Gets iterator over all nodes of specified type in the graph with mutable access.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes to iterate over.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the nodes and their mutable write access.
+    pub fn iter_mut<'a, T: 'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (AnyIndex, ValueWriteAccess<'a, T>)> {
+        self.nodes.arena::<T>().into_iter().flat_map(|arena| {
+            arena
+                .indices()
+                .map(|index| AnyIndex::new(index, arena.type_hash()))
+                .zip(arena.iter_mut::<T>())
+        })
+    }
+
+    /// Calls provided function for every node of specified type, giving it
+    /// mutable access to the whole graph.
+    /// Matching indices are collected before iteration, so nodes inserted
+    /// during iteration are not visited, and nodes removed during iteration
+    /// are skipped.
+    ///
+    /// # Arguments
+    /// * `f` - The function to call with node index and graph.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes to iterate over.
+    pub fn for_each_mut<T>(&mut self, mut f: impl FnMut(AnyIndex, &mut Graph)) {
+        let indices = self
+            .nodes
+            .arena::<T>()
+            .map(|arena| {
+                arena
+                    .indices()
+                    .map(|index| AnyIndex::new(index, arena.type_hash()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for index in indices {
+            if self.contains(index) {
+                f(index, self);
+            }
+        }
+    }
+
+    /// Overwrites target nodes of specified relation category with values
+    /// derived from their source nodes. All values are computed from current
+    /// source nodes first and only then written to target nodes, so writes do
+    /// not affect sources read in the same call.
+    ///
+    /// # Arguments
+    /// * `f` - The function deriving target value from source node.
+    ///
+    /// # Type Parameters
+    /// * `R` - The type of the relation category.
+    /// * `Src` - The type of the source nodes.
+    /// * `Dst` - The type of the target nodes.
+    pub fn map_relation<R, Src, Dst>(&mut self, f: impl Fn(&Src) -> Dst) {
+        let values = self
+            .relations_outgoing_all::<R>()
+            .filter(|(from, to)| self.is::<Src>(*from) && self.is::<Dst>(*to))
+            .filter_map(|(from, to)| Some((to, f(&*self.read::<Src>(from).ok()?))))
+            .collect::<Vec<_>>();
+        for (to, value) in values {
+            if let Ok(mut target) = self.write::<Dst>(to) {
+                *target = value;
+            }
+        }
+    }
+
+    /// Gets iterator over relations of specified relation category together
+    /// with data of both related nodes. Relations whose nodes are not of the
+    /// specified types are skipped.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    /// * `S` - The type of the source nodes.
+    /// * `D` - The type of the target nodes.
+    ///
+    /// # Returns
+    /// An iterator over indices and read access of source and target nodes.
+    pub fn query_edges<'a, T, S: 'a, D: 'a>(
+        &'a self,
+    ) -> impl Iterator<
+        Item = (
+            AnyIndex,
+            ValueReadAccess<'a, S>,
+            AnyIndex,
+            ValueReadAccess<'a, D>,
+        ),
+    > {
+        self.relations_outgoing_all::<T>()
+            .filter(|(from, to)| self.is::<S>(*from) && self.is::<D>(*to))
+            .filter_map(|(from, to)| {
+                Some((
+                    from,
+                    self.read::<S>(from).ok()?,
+                    to,
+                    self.read::<D>(to).ok()?,
+                ))
+            })
+    }
+
+    /// Gets iterator over relations of specified relation category together
+    /// with read access to source nodes and write access to target nodes.
+    /// Relations whose nodes are not of the specified types, or whose nodes
+    /// cannot be accessed at the same time, are skipped.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    /// * `S` - The type of the source nodes.
+    /// * `D` - The type of the target nodes.
+    ///
+    /// # Returns
+    /// An iterator over indices and access of source and target nodes.
+    pub fn query_edges_mut<'a, T, S: 'a, D: 'a>(
+        &'a self,
+    ) -> impl Iterator<
+        Item = (
+            AnyIndex,
+            ValueReadAccess<'a, S>,
+            AnyIndex,
+            ValueWriteAccess<'a, D>,
+        ),
+    > {
+        self.relations_outgoing_all::<T>()
+            .filter(|(from, to)| self.is::<S>(*from) && self.is::<D>(*to))
+            .filter_map(|(from, to)| {
+                let source = self.read::<S>(from).ok()?;
+                Some((from, source, to, self.write::<D>(to).ok()?))
+            })
+    }
+
+    /// Gets iterator over all node indices in the graph.
+    ///
+    /// # Returns
+    /// An iterator over the indices of the nodes.
+    pub fn indices(&self) -> impl Iterator<Item = AnyIndex> + '_ {
+        self.nodes.indices()
+    }
+
+    /// Finds first node reachable from both specified nodes, by performing
+    /// simultaneous breadth-first search from both nodes over outgoing
+    /// relations.
+    ///
+    /// # Arguments
+    /// * `a` - The index of the first node.
+    /// * `b` - The index of the second node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// The index of the first common descendant, or `None` if there is none.
+    pub fn first_common_descendant<T>(&self, a: AnyIndex, b: AnyIndex) -> Option<AnyIndex> {
+        if a == b {
+            return Some(a);
+        }
+        let type_hash = TypeHash::of::<T>();
+        let mut visited = [HashSet::from([a]), HashSet::from([b])];
+        let mut frontiers = [vec![a], vec![b]];
+        while frontiers.iter().any(|frontier| !frontier.is_empty()) {
+            for side in 0..2 {
+                let mut next = Vec::default();
+                for source in std::mem::take(&mut frontiers[side]) {
+                    for target in self.relations_outgoing_raw(source, type_hash) {
+                        if visited[1 - side].contains(&target) {
+                            return Some(target);
+                        }
+                        if visited[side].insert(target) {
+                            next.push(target);
+                        }
+                    }
+                }
+                frontiers[side] = next;
+            }
+        }
+        None
+    }
+
+    /// Counts distinct nodes reachable from the specified node through the
+    /// specified relation category, excluding the node itself.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// The number of descendants.
+    pub fn descendant_count<T>(&self, index: AnyIndex) -> usize {
+        self.relations_traverse::<T>(index)
+            .filter(|item| *item != index)
+            .count()
+    }
+
+    /// Counts descendants of all nodes participating in the specified
+    /// relation category in a single post-order pass, reusing descendants of
+    /// targets for their sources. Falls back to traversal per node if
+    /// relations contain cycles.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A map from node index to its number of descendants.
+    pub fn descendant_counts<T>(&self) -> HashMap<AnyIndex, usize> {
+        let Ok(layers) = self.kahn_layers::<T>() else {
+            return self
+                .relations_outgoing_all::<T>()
+                .flat_map(|(from, to)| [from, to])
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .map(|index| (index, self.descendant_count::<T>(index)))
+                .collect();
+        };
+        let mut descendants = HashMap::<AnyIndex, HashSet<AnyIndex>>::default();
+        for index in layers.into_iter().rev().flatten() {
+            let mut result = HashSet::default();
+            for target in self.relations_outgoing::<T>(index) {
+                result.insert(target);
+                if let Some(items) = descendants.get(&target) {
+                    result.extend(items.iter().copied());
+                }
+            }
+            descendants.insert(index, result);
+        }
+        descendants
+            .into_iter()
+            .map(|(index, items)| (index, items.len()))
+            .collect()
+    }
+
+    /// Finds ancestor of the specified node by following incoming relations of
+    /// the specified relation category `n` times. Meant for single-parent
+    /// trees; if node has many sources, the one with the lowest index is
+    /// followed, so result does not depend on relation storage order.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the node.
+    /// * `n` - The number of relations to follow.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// The index of the ancestor, or `None` if chain of relations runs out or
+    /// forms a cycle.
+    pub fn nth_ancestor<T>(&self, index: AnyIndex, n: usize) -> Option<AnyIndex> {
+        let mut visited = HashSet::from([index]);
+        let mut current = index;
+        for _ in 0..n {
+            current = self.relations_incoming::<T>(current).min()?;
+            if !visited.insert(current) {
+                return None;
+            }
+        }
+        Some(current)
+    }
+
+    /// Finds shortest path between two nodes over the specified relation
+    /// category, in terms of number of relations.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A list of indices from source to target node, or `None` if target is
+    /// not reachable.
+    pub fn shortest_path<T>(&self, from: AnyIndex, to: AnyIndex) -> Option<Vec<AnyIndex>> {
+        self.shortest_path_avoiding::<T>(from, to, &HashSet::default(), &HashSet::default())
+    }
+
+    /// Finds up to `k` loopless shortest paths between two nodes over the
+    /// specified relation category, using Yen's algorithm.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    /// * `k` - The maximum number of paths to find.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A list of paths ordered by increasing length.
+    pub fn k_shortest_paths<T>(
+        &self,
+        from: AnyIndex,
+        to: AnyIndex,
+        k: usize,
+    ) -> Vec<Vec<AnyIndex>> {
+        let mut result = Vec::<Vec<AnyIndex>>::default();
+        if k == 0 {
+            return result;
+        }
+        let Some(path) = self.shortest_path::<T>(from, to) else {
+            return result;
+        };
+        result.push(path);
+        let mut candidates = Vec::<Vec<AnyIndex>>::default();
+        while result.len() < k {
+            let previous = result.last().unwrap().clone();
+            for spur in 0..previous.len().saturating_sub(1) {
+                let root = &previous[..=spur];
+                let blocked_edges = result
+                    .iter()
+                    .filter(|path| path.len() > spur + 1 && &path[..=spur] == root)
+                    .map(|path| (path[spur], path[spur + 1]))
+                    .collect::<HashSet<_>>();
+                let blocked_nodes = root[..spur].iter().copied().collect::<HashSet<_>>();
+                if let Some(spur_path) = self.shortest_path_avoiding::<T>(
+                    previous[spur],
+                    to,
+                    &blocked_nodes,
+                    &blocked_edges,
+                ) {
+                    let path = root[..spur]
+                        .iter()
+                        .copied()
+                        .chain(spur_path)
+                        .collect::<Vec<_>>();
+                    if !result.contains(&path) && !candidates.contains(&path) {
+                        candidates.push(path);
+                    }
+                }
+            }
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, path)| path.len())
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            result.push(candidates.remove(best));
+        }
+        result
+    }
+
+    fn shortest_path_avoiding<T>(
+        &self,
+        from: AnyIndex,
+        to: AnyIndex,
+        blocked_nodes: &HashSet<AnyIndex>,
+        blocked_edges: &HashSet<(AnyIndex, AnyIndex)>,
+    ) -> Option<Vec<AnyIndex>> {
+        let mut parents = HashMap::<AnyIndex, AnyIndex>::default();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(source) = queue.pop_front() {
+            if source == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(parent) = parents.get(&current).copied() {
+                    path.push(parent);
+                    current = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for target in self.relations_outgoing::<T>(source) {
+                if blocked_nodes.contains(&target)
+                    || blocked_edges.contains(&(source, target))
+                    || !visited.insert(target)
+                {
+                    continue;
+                }
+                parents.insert(target, source);
+                queue.push_back(target);
+            }
+        }
+        None
+    }
+
+    /// Finds all cycles in the graph for the specified relation category.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// An iterator over list of indices representing the cycle path.
+    pub fn find_cycles<T>(&self) -> impl Iterator<Item = Vec<AnyIndex>> + '_ {
+        self.nodes.indices().filter_map(|index| {
+            let cycle = self.find_cycle::<T>(index);
+            if cycle.is_empty() { None } else { Some(cycle) }
+        })
+    }
+
+    /// Finds a cycle in the graph starting from the specified index.
+    ///
+    /// # Arguments
+    /// * `index` - The index to start searching for a cycle.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A list of indices representing the cycle path.
+    pub fn find_cycle<T>(&self, index: AnyIndex) -> Vec<AnyIndex> {
+        fn walk<T>(
+            visited: &mut HashSet<AnyIndex>,
+            path: &mut Vec<AnyIndex>,
+            graph: &Graph,
+            source: AnyIndex,
+        ) -> Option<usize> {
+            if visited.contains(&source) {
+                return None;
+            }
+            visited.insert(source);
+            path.push(source);
+            for target in graph.relations_outgoing::<T>(source) {
+                if let Some(index) = path.iter().position(|item| *item == target) {
+                    return Some(index);
+                }
+                if let Some(index) = walk::<T>(visited, path, graph, target) {
+                    return Some(index);
+                }
+            }
+            path.pop();
+            None
+        }
+
+        let mut visited = HashSet::default();
+        let mut path = Vec::default();
+        if let Some(index) = walk::<T>(&mut visited, &mut path, self, index) {
+            return path[index..].to_vec();
+        }
+        path
+    }
+
+    /// Groups nodes participating in specified relation category into
+    /// topological layers: first layer contains nodes without incoming
+    /// relations, every next layer contains nodes whose all sources are in
+    /// previous layers.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing list of layers, or a cycle path if relations of
+    /// specified category do not form a directed acyclic graph.
+    pub fn kahn_layers<T>(&self) -> Result<Vec<Vec<AnyIndex>>, Vec<AnyIndex>> {
+        let mut degrees = HashMap::<AnyIndex, usize>::default();
+        for (from, to) in self.relations_outgoing_all::<T>() {
+            degrees.entry(from).or_default();
+            *degrees.entry(to).or_default() += 1;
+        }
+        let mut layers = Vec::<Vec<AnyIndex>>::default();
+        let mut layer = degrees
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+        let mut processed = 0;
+        while !layer.is_empty() {
+            layer.sort();
+            let mut next = Vec::default();
+            for source in &layer {
+                for target in self.relations_outgoing::<T>(*source) {
+                    if let Some(degree) = degrees.get_mut(&target) {
+                        *degree -= 1;
+                        if *degree == 0 {
+                            next.push(target);
+                        }
+                    }
+                }
+            }
+            processed += layer.len();
+            layers.push(std::mem::replace(&mut layer, next));
+        }
+        if processed < degrees.len() {
+            let remaining = degrees
+                .into_iter()
+                .filter(|(_, degree)| *degree > 0)
+                .map(|(index, _)| index)
+                .collect();
+            return Err(self.find_cycle_among::<T>(&remaining));
+        }
+        Ok(layers)
+    }
+
+    /// Sorts nodes participating in specified relation category in
+    /// topological order, so every node comes before nodes it relates to.
+    /// Only nodes being source or target of at least one relation of that
+    /// category are included; disconnected subgraphs are all included.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing sorted nodes, or a cycle path if relations of
+    /// specified category do not form a directed acyclic graph.
+    pub fn topological_sort<T>(&self) -> Result<Vec<AnyIndex>, Vec<AnyIndex>> {
+        Ok(self.kahn_layers::<T>()?.into_iter().flatten().collect())
+    }
+
+    /// Sorts nodes participating in specified relation category in reverse
+    /// topological order, so every node comes after nodes it relates to:
+    /// leaves first, roots last.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A result containing sorted nodes, or a cycle path if relations of
+    /// specified category do not form a directed acyclic graph.
+    pub fn reverse_topological_sort<T>(&self) -> Result<Vec<AnyIndex>, Vec<AnyIndex>> {
+        Ok(self
+            .kahn_layers::<T>()?
+            .into_iter()
+            .rev()
+            .flatten()
+            .collect())
+    }
+
+    fn find_cycle_among<T>(&self, remaining: &HashSet<AnyIndex>) -> Vec<AnyIndex> {
+        let Some(mut current) = remaining.iter().next().copied() else {
+            return Default::default();
+        };
+        let mut visited = HashSet::<AnyIndex>::default();
+        while visited.insert(current) {
+            match self
+                .relations_incoming::<T>(current)
+                .find(|index| remaining.contains(index))
+            {
+                Some(index) => current = index,
+                None => break,
+            }
+        }
+        self.find_cycle::<T>(current)
+    }
+
+    /// Traverses relations of the specified relation category from each of
+    /// the specified source nodes separately.
+    ///
+    /// # Arguments
+    /// * `sources` - The indices of the source nodes.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A map from each source node to the set of nodes reachable from it,
+    /// including the source node itself.
+    pub fn reachable_sets<T>(&self, sources: &[AnyIndex]) -> HashMap<AnyIndex, HashSet<AnyIndex>> {
+        sources
+            .iter()
+            .map(|source| (*source, self.relations_traverse::<T>(*source).collect()))
+            .collect()
+    }
+
+    /// Partitions nodes into connected components, treating relations of the
+    /// specified relation category as undirected. Only nodes being source or
+    /// target of at least one relation of that category are included, so
+    /// isolated nodes do not form their own components.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A list of components, each being a sorted list of node indices.
+    pub fn connected_components<T>(&self) -> Vec<Vec<AnyIndex>> {
+        let mut nodes = self
+            .relations_outgoing_all::<T>()
+            .flat_map(|(from, to)| [from, to])
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        let mut visited = HashSet::<AnyIndex>::default();
+        let mut result = Vec::default();
+        for start in nodes {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(source) = queue.pop_front() {
+                for target in self
+                    .relations_outgoing::<T>(source)
+                    .chain(self.relations_incoming::<T>(source))
+                {
+                    if visited.insert(target) {
+                        component.push(target);
+                        queue.push_back(target);
+                    }
+                }
+            }
+            component.sort();
+            result.push(component);
+        }
+        result
+    }
+
+    /// Computes condensation of the specified relation category, that is
+    /// directed acyclic graph whose nodes are strongly connected components.
+    /// Only nodes being source or target of at least one relation of that
+    /// category are included.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A tuple of strongly connected components in topological order, each
+    /// being a sorted list of node indices, and a sorted list of relations
+    /// between components, as pairs of component positions.
+    pub fn condensation<T>(&self) -> (Vec<Vec<AnyIndex>>, Vec<(usize, usize)>) {
+        let mut nodes = self
+            .relations_outgoing_all::<T>()
+            .flat_map(|(from, to)| [from, to])
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        let mut visited = HashSet::<AnyIndex>::default();
+        let mut order = Vec::with_capacity(nodes.len());
+        for start in nodes {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut stack = vec![(
+                start,
+                self.relations_outgoing::<T>(start).collect::<Vec<_>>(),
+                0,
+            )];
+            while let Some((node, targets, next)) = stack.last_mut() {
+                if let Some(target) = targets.get(*next).copied() {
+                    *next += 1;
+                    if visited.insert(target) {
+                        stack.push((target, self.relations_outgoing::<T>(target).collect(), 0));
+                    }
+                } else {
+                    order.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+        let mut components = Vec::<Vec<AnyIndex>>::default();
+        let mut assigned = HashMap::<AnyIndex, usize>::default();
+        for start in order.into_iter().rev() {
+            if assigned.contains_key(&start) {
+                continue;
+            }
+            let id = components.len();
+            assigned.insert(start, id);
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(target) = queue.pop_front() {
+                for source in self.relations_incoming::<T>(target) {
+                    if let Entry::Vacant(entry) = assigned.entry(source) {
+                        entry.insert(id);
+                        component.push(source);
+                        queue.push_back(source);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        let mut relations = self
+            .relations_outgoing_all::<T>()
+            .map(|(from, to)| (assigned[&from], assigned[&to]))
+            .filter(|(from, to)| from != to)
+            .collect::<Vec<_>>();
+        relations.sort();
+        relations.dedup();
+        (components, relations)
+    }
+
+    /// Finds bridges in undirected view of the specified relation category,
+    /// that is relations whose removal increases number of connected
+    /// components.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A sorted list of bridge relations (source, target).
+    pub fn bridges<T>(&self) -> Vec<(AnyIndex, AnyIndex)> {
+        let mut result = self
+            .low_link::<T>()
+            .0
+            .into_iter()
+            .map(|(a, b)| {
+                if self.are_related::<T>(a, b) {
+                    (a, b)
+                } else {
+                    (b, a)
+                }
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    /// Finds articulation points in undirected view of the specified relation
+    /// category, that is nodes whose removal increases number of connected
+    /// components.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A set of articulation point indices.
+    pub fn articulation_points<T>(&self) -> HashSet<AnyIndex> {
+        self.low_link::<T>().1
+    }
+
+    fn low_link<T>(&self) -> (Vec<(AnyIndex, AnyIndex)>, HashSet<AnyIndex>) {
+        let mut adjacency = HashMap::<AnyIndex, Vec<AnyIndex>>::default();
+        for (from, to) in self.relations_outgoing_all::<T>() {
+            if from != to {
+                adjacency.entry(from).or_default().push(to);
+                adjacency.entry(to).or_default().push(from);
+            }
+        }
+        for neighbors in adjacency.values_mut() {
+            neighbors.sort();
+            neighbors.dedup();
+        }
+        let mut nodes = adjacency.keys().copied().collect::<Vec<_>>();
+        nodes.sort();
+        let mut discovery = HashMap::<AnyIndex, usize>::default();
+        let mut low = HashMap::<AnyIndex, usize>::default();
+        let mut bridges = Vec::default();
+        let mut articulation_points = HashSet::default();
+        for root in nodes {
+            if discovery.contains_key(&root) {
+                continue;
+            }
+            discovery.insert(root, discovery.len());
+            low.insert(root, discovery[&root]);
+            let mut stack = vec![(root, None, 0)];
+            let mut root_children = 0;
+            while let Some((node, parent, next)) = stack.last_mut() {
+                let (node, parent) = (*node, *parent);
+                if let Some(target) = adjacency[&node].get(*next).copied() {
+                    *next += 1;
+                    if Some(target) == parent {
+                        continue;
+                    }
+                    if let Some(time) = discovery.get(&target).copied() {
+                        let value = low[&node].min(time);
+                        low.insert(node, value);
+                    } else {
+                        discovery.insert(target, discovery.len());
+                        low.insert(target, discovery[&target]);
+                        stack.push((target, Some(node), 0));
+                    }
+                } else {
+                    stack.pop();
+                    if let Some(parent) = parent {
+                        let value = low[&parent].min(low[&node]);
+                        low.insert(parent, value);
+                        if low[&node] > discovery[&parent] {
+                            bridges.push((parent, node));
+                        }
+                        if parent == root {
+                            root_children += 1;
+                        } else if low[&node] >= discovery[&parent] {
+                            articulation_points.insert(parent);
+                        }
+                    }
+                }
+            }
+            if root_children > 1 {
+                articulation_points.insert(root);
+            }
+        }
+        (bridges, articulation_points)
+    }
+
+    /// Computes edge betweenness of relations of the specified relation
+    /// category, treating them as undirected. Betweenness of a relation is the
+    /// sum, over all pairs of nodes, of fractions of shortest paths between
+    /// them that pass through that relation. Relations in both directions
+    /// between the same nodes share the same value, self-relations are
+    /// ignored.
+    /// It runs breadth-first search from every node, so its complexity is
+    /// `O(V * E)`, where `V` is number of nodes and `E` is number of relations.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A map from source and target indices of relations to their betweenness.
+    pub fn edge_betweenness<T>(&self) -> HashMap<(AnyIndex, AnyIndex), f64> {
+        let mut neighbors = HashMap::<AnyIndex, Vec<AnyIndex>>::default();
+        for (from, to) in self.relations_outgoing_all::<T>() {
+            if from == to {
+                continue;
+            }
+            neighbors.entry(from).or_default().push(to);
+            neighbors.entry(to).or_default().push(from);
+        }
+        for list in neighbors.values_mut() {
+            list.sort();
+            list.dedup();
+        }
+        let key = |a: AnyIndex, b: AnyIndex| if a < b { (a, b) } else { (b, a) };
+        let mut scores = HashMap::<(AnyIndex, AnyIndex), f64>::default();
+        for source in neighbors.keys().copied() {
+            let mut order = Vec::with_capacity(neighbors.len());
+            let mut predecessors = HashMap::<AnyIndex, Vec<AnyIndex>>::default();
+            let mut paths = HashMap::<AnyIndex, f64>::from([(source, 1.0)]);
+            let mut distances = HashMap::<AnyIndex, usize>::from([(source, 0)]);
+            let mut queue = VecDeque::from([source]);
+            while let Some(current) = queue.pop_front() {
+                order.push(current);
+                let distance = distances[&current];
+                for next in &neighbors[&current] {
+                    let next_distance = *distances.entry(*next).or_insert_with(|| {
+                        queue.push_back(*next);
+                        distance + 1
+                    });
+                    if next_distance == distance + 1 {
+                        *paths.entry(*next).or_default() += paths[&current];
+                        predecessors.entry(*next).or_default().push(current);
+                    }
+                }
+            }
+            let mut dependencies = HashMap::<AnyIndex, f64>::default();
+            for target in order.into_iter().rev() {
+                let dependency = dependencies.get(&target).copied().unwrap_or_default();
+                for previous in predecessors.remove(&target).unwrap_or_default() {
+                    let value = paths[&previous] / paths[&target] * (1.0 + dependency);
+                    *scores.entry(key(previous, target)).or_default() += value;
+                    *dependencies.entry(previous).or_default() += value;
+                }
+            }
+        }
+        self.relations_outgoing_all::<T>()
+            .filter(|(from, to)| from != to)
+            .map(|(from, to)| {
+                let score = scores.get(&key(from, to)).copied().unwrap_or_default();
+                ((from, to), score / 2.0)
+            })
+            .collect()
+    }
+
+    /// Computes PageRank of nodes participating in the specified relation
+    /// category. Rank of nodes without outgoing relations is redistributed
+    /// uniformly among all nodes.
+    ///
+    /// # Arguments
+    /// * `damping` - The probability of following a relation instead of
+    ///   jumping to random node, usually `0.85`.
+    /// * `iterations` - The number of power iterations to perform.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A map from node index to its score, with scores summing to one.
+    pub fn pagerank<T>(&self, damping: f64, iterations: usize) -> HashMap<AnyIndex, f64> {
+        let mut outgoing = HashMap::<AnyIndex, Vec<AnyIndex>>::default();
+        for (from, to) in self.relations_outgoing_all::<T>() {
+            outgoing.entry(from).or_default().push(to);
+            outgoing.entry(to).or_default();
+        }
+        if outgoing.is_empty() {
+            return Default::default();
+        }
+        let count = outgoing.len() as f64;
+        let mut ranks = outgoing
+            .keys()
+            .map(|index| (*index, 1.0 / count))
+            .collect::<HashMap<_, _>>();
+        for _ in 0..iterations {
+            let dangling = outgoing
+                .iter()
+                .filter(|(_, targets)| targets.is_empty())
+                .map(|(index, _)| ranks[index])
+                .sum::<f64>();
+            let base = (1.0 - damping + damping * dangling) / count;
+            let mut next = outgoing
+                .keys()
+                .map(|index| (*index, base))
+                .collect::<HashMap<_, _>>();
+            for (source, targets) in &outgoing {
+                if targets.is_empty() {
+                    continue;
+                }
+                let share = damping * ranks[source] / targets.len() as f64;
+                for target in targets {
+                    if let Some(rank) = next.get_mut(target) {
+                        *rank += share;
+                    }
+                }
+            }
+            ranks = next;
+        }
+        let total = ranks.values().sum::<f64>();
+        if total > 0.0 {
+            for rank in ranks.values_mut() {
+                *rank /= total;
+            }
+        }
+        ranks
     }
 
-    /// Gets iterator over all incoming relations in graph, no matter the target,
-    /// with specified relation category.
+    /// Serializes graph into prefab.
     ///
     /// # Arguments
-    /// * `type_hash` - The type hash of the relation category.
+    /// * `serialization` - The serialization registry to use for serialization.
+    /// * `registry` - The registry to use for type lookups.
     ///
     /// # Returns
-    /// An iterator over tuples containing the indices of the related nodes.
-    pub fn relations_incomming_all_raw(
+    /// A result containing the prefab or an error.
+    pub fn serialize_with(
         &self,
-        type_hash: TypeHash,
-    ) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
-        self.relations
-            .get(&type_hash)
-            .into_iter()
-            .flat_map(move |relations| relations.iter_incoming())
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<Prefab, PrefabError> {
+        Prefab::from_graph(self, serialization, registry)
     }
 
-    /// Gets iterator over incoming relations to the specified target node
-    /// with any relation category.
+    /// Deserializes graph from prefab.
     ///
     /// # Arguments
-    /// * `to` - The index of the target node.
+    /// * `prefab` - The prefab to deserialize graph from.
+    /// * `serialization` - The serialization registry to use for deserialization.
+    /// * `registry` - The registry to use for type lookups.
     ///
     /// # Returns
-    /// An iterator over the indices of the source nodes.
-    pub fn relations_incomming_any(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        self.relations
-            .values()
-            .flat_map(move |relations| relations.incoming(to))
+    /// A result containing the graph or an error.
+    pub fn deserialize_from(
+        prefab: &Prefab,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<Self, PrefabError> {
+        Ok(prefab.to_graph(serialization, registry)?.0)
     }
 
-    /// Gets traverse iterator over all relations from the specified source node
-    /// with the specified relation category.
+    /// Creates snapshot of the graph that implements `Serialize`, so graph
+    /// can be passed directly to any serde serializer.
     ///
     /// # Arguments
-    /// * `from` - The index of the source node.
-    ///
-    /// # Type Parameters
-    /// * `T` - The type of the relation category.
+    /// * `serialization` - The serialization registry to use for serialization.
+    /// * `registry` - The registry to use for type lookups.
     ///
     /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn relations_traverse<T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        GraphTraverseIter::new::<T>(self, from)
+    /// A `GraphSnapshot` of this graph.
+    pub fn snapshot<'a>(
+        &'a self,
+        serialization: &'a SerializationRegistry,
+        registry: &'a Registry,
+    ) -> GraphSnapshot<'a> {
+        GraphSnapshot {
+            graph: self,
+            serialization,
+            registry,
+        }
     }
 
-    /// Gets traverse iterator over all relations from the specified source node
-    /// with the specified relation category.
+    /// Creates deserialization seed that produces graph from its serialized
+    /// snapshot with any serde deserializer.
     ///
     /// # Arguments
-    /// * `from` - The index of the source node.
-    /// * `type_hash` - The type hash of the relation category.
+    /// * `serialization` - The serialization registry to use for deserialization.
+    /// * `registry` - The registry to use for type lookups.
     ///
     /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn relations_traverse_raw(
-        &self,
-        from: AnyIndex,
-        type_hash: TypeHash,
-    ) -> impl Iterator<Item = AnyIndex> + '_ {
-        GraphTraverseIter::new_raw(self, from, type_hash)
+    /// A `GraphSnapshotSeed` for deserialization.
+    pub fn snapshot_seed<'a>(
+        serialization: &'a SerializationRegistry,
+        registry: &'a Registry,
+    ) -> GraphSnapshotSeed<'a> {
+        GraphSnapshotSeed {
+            serialization,
+            registry,
+        }
     }
 
-    /// Gets traverse iterator over all relations from the specified source node
-    /// with any relation category.
+    /// Computes hash of graph content, that depends only on node data and
+    /// relations topology, not on node indices or insertion order.
+    /// See `Prefab::content_hash` for details.
     ///
     /// # Arguments
-    /// * `from` - The index of the source node.
+    /// * `serialization` - The serialization registry to use for serialization.
+    /// * `registry` - The registry to use for type lookups.
     ///
     /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn relations_traverse_any(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        GraphTraverseAnyIter::new(self, from)
+    /// A result containing the content hash or an error.
+    pub fn content_hash(
+        &self,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<u64, PrefabError> {
+        Ok(Prefab::from_graph(self, serialization, registry)?.content_hash())
     }
 
-    /// Finds all nodes of the specified type that are related to the specified
-    /// source node with the specified relation category.
+    /// Extracts part of the graph reachable from the specified root node into
+    /// a new graph. Nodes hold arbitrary data, so they are copied by
+    /// serializing and deserializing them, the same way prefabs do.
+    /// Only relations between copied nodes are recreated, relations to nodes
+    /// outside of the reachable set are dropped. Tags of copied nodes,
+    /// relation payloads and relation cardinalities are preserved. Payloads
+    /// are copied through serialization too, so relation categories with
+    /// payloads have to be registered.
     ///
     /// # Arguments
-    /// * `from` - The index of the source node.
+    /// * `root` - The index of the root node.
+    /// * `serialization` - The serialization registry to use for copying nodes.
+    /// * `registry` - The registry to use for type lookups.
     ///
     /// # Type Parameters
-    /// * `R` - The type of the relation category.
-    /// * `T` - The type of the target nodes.
+    /// * `T` - The type of the relation category to traverse.
     ///
     /// # Returns
-    /// An iterator over the indices of the target nodes.
-    pub fn find<R, T>(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + '_ {
-        self.relations_outgoing::<R>(from)
-            .filter(|index| self.is::<T>(*index))
+    /// A result containing the new graph and a mapping of old indices to new
+    /// indices, or an error.
+    pub fn subgraph<T>(
+        &self,
+        root: AnyIndex,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<(Graph, HashMap<AnyIndex, AnyIndex>), PrefabError> {
+        let mut indices = self.relations_traverse::<T>(root).collect::<Vec<_>>();
+        indices.sort();
+        let mut mappings = HashMap::<AnyIndex, AnyIndex>::default();
+        let mut nodes = AnyArena::default();
+        for index in indices {
+            let type_ = registry
+                .find_type(TypeQuery {
+                    type_hash: Some(index.type_hash()),
+                    ..Default::default()
+                })
+                .ok_or_else(|| PrefabError::CouldNotFindType(index.type_hash()))?;
+            unsafe {
+                let (source, _) = self.read_dynamic(index)?;
+                let data = serialization
+                    .dynamic_serialize_from(type_.type_hash(), source, registry)
+                    .map_err(|_| PrefabError::CouldNotSerializeType {
+                        type_name: type_.name().to_owned(),
+                        module_name: type_.module_name().map(|name| name.to_owned()),
+                    })?;
+                let arena =
+                    nodes.ensure_arena_raw(type_.type_hash(), *type_.layout(), type_.finalizer());
+                let (new_index, memory) = arena.allocate();
+                type_.initialize(memory.cast::<_>());
+                serialization
+                    .dynamic_deserialize_to(type_.type_hash(), memory, &data, true, registry)
+                    .map_err(|_| PrefabError::CouldNotDeserializeType {
+                        type_name: type_.name().to_owned(),
+                        module_name: type_.module_name().map(|name| name.to_owned()),
+                    })?;
+                mappings.insert(index, AnyIndex::new(new_index, type_.type_hash()));
+            }
+        }
+        let mut relations = HashMap::<TypeHash, RelationsTable>::default();
+        for (type_hash, source) in &self.relations {
+            let type_ = if source.has_payloads() {
+                Some(
+                    registry
+                        .find_type(TypeQuery {
+                            type_hash: Some(*type_hash),
+                            ..Default::default()
+                        })
+                        .ok_or(PrefabError::CouldNotFindType(*type_hash))?,
+                )
+            } else {
+                None
+            };
+            let mut table = RelationsTable::default();
+            for (from, to) in source.iter_outgoing() {
+                let (Some(new_from), Some(new_to)) = (mappings.get(&from), mappings.get(&to))
+                else {
+                    continue;
+                };
+                table.insert(*new_from, *new_to);
+                if let Some(type_) = &type_
+                    && let Some(payload) =
+                        serialize_payload(source, from, to, type_, serialization, registry)?
+                {
+                    deserialize_payload(
+                        &mut table,
+                        *new_from,
+                        *new_to,
+                        type_,
+                        &payload,
+                        serialization,
+                        registry,
+                    )?;
+                }
+            }
+            if !table.is_empty() {
+                relations.insert(*type_hash, table);
+            }
+        }
+        let tags = self
+            .tags
+            .iter()
+            .filter_map(|(type_hash, indices)| {
+                let indices = indices
+                    .iter()
+                    .filter_map(|index| mappings.get(index).copied())
+                    .collect::<HashSet<_>>();
+                (!indices.is_empty()).then_some((*type_hash, indices))
+            })
+            .collect();
+        Ok((
+            Graph {
+                nodes,
+                relations,
+                tags,
+                cardinalities: self.cardinalities.clone(),
+                ..Default::default()
+            },
+            mappings,
+        ))
     }
+}
 
-    /// Performs query on the graph using the specified index.
-    pub fn query<'a, Fetch: QueryFetch<'a>>(&'a self, index: AnyIndex) -> QueryIter<'a, Fetch> {
-        QueryIter::new(self, index)
-    }
+/// Read-only view of single relation category edges.
+#[derive(Clone, Copy)]
+pub struct RelationTableView<'a> {
+    table: &'a RelationsTable,
+}
 
-    /// Gets iterator over all nodes of specified type in the graph.
-    ///
-    /// # Type Parameters
-    /// * `T` - The type of the nodes to iterate over.
+impl<'a> RelationTableView<'a> {
+    /// Checks if two nodes are related.
     ///
-    /// # Returns
-    /// An iterator over the indices of the nodes and their read access.
-    pub fn iter<'a, T: 'a>(&'a self) -> impl Iterator<Item = (AnyIndex, ValueReadAccess<'a, T>)> {
-        self.nodes.arena::<T>().into_iter().flat_map(|arena| {
-            arena
-                .indices()
-                .map(|index| AnyIndex::new(index, arena.type_hash()))
-                .zip(arena.iter::<T>())
-        })
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    pub fn contains(&self, from: AnyIndex, to: AnyIndex) -> bool {
+        self.table.contains(from, to)
     }
 
-    /// Gets iterator over all nodes of specified type in the graph with mutable access.
-    ///
-    /// # Type Parameters
-    /// * `T` - The type of the nodes to iterate over.
+    /// Gets iterator over target nodes of the specified source node.
     ///
-    /// # Returns
-    /// An iterator over the indices of the nodes and their mutable write access.
-    pub fn iter_mut<'a, T: 'a>(
-        &'a self,
-    ) -> impl Iterator<Item = (AnyIndex, ValueWriteAccess<'a, T>)> {
-        self.nodes.arena::<T>().into_iter().flat_map(|arena| {
-            arena
-                .indices()
-                .map(|index| AnyIndex::new(index, arena.type_hash()))
-                .zip(arena.iter_mut::<T>())
-        })
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    pub fn outgoing(&self, from: AnyIndex) -> impl Iterator<Item = AnyIndex> + 'a {
+        self.table.outgoing(from)
     }
 
-    /// Gets iterator over all node indices in the graph.
+    /// Gets iterator over source nodes of the specified target node.
     ///
-    /// # Returns
-    /// An iterator over the indices of the nodes.
-    pub fn indices(&self) -> impl Iterator<Item = AnyIndex> + '_ {
-        self.nodes.indices()
+    /// # Arguments
+    /// * `to` - The index of the target node.
+    pub fn incoming(&self, to: AnyIndex) -> impl Iterator<Item = AnyIndex> + 'a {
+        self.table.incoming(to)
     }
 
-    /// Finds all cycles in the graph for the specified relation category.
+    /// Gets iterator over all edges as pairs of source and target indices.
+    pub fn iter(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + 'a {
+        self.table.iter_outgoing()
+    }
+}
+
+/// Read-only view of relations of single node across all categories.
+pub struct NodeView<'a> {
+    index: AnyIndex,
+    outgoing: Vec<(TypeHash, &'a HashSet<AnyIndex>)>,
+    incoming: Vec<(TypeHash, &'a HashSet<AnyIndex>)>,
+}
+
+impl<'a> NodeView<'a> {
+    /// Gets the index of viewed node.
+    pub fn index(&self) -> AnyIndex {
+        self.index
+    }
+
+    /// Gets iterator over target nodes with specified relation category.
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
-    ///
-    /// # Returns
-    /// An iterator over list of indices representing the cycle path.
-    pub fn find_cycles<T>(&self) -> impl Iterator<Item = Vec<AnyIndex>> + '_ {
-        self.nodes.indices().filter_map(|index| {
-            let cycle = self.find_cycle::<T>(index);
-            if cycle.is_empty() { None } else { Some(cycle) }
-        })
+    pub fn outgoing<T>(&self) -> impl Iterator<Item = AnyIndex> + 'a {
+        self.outgoing_raw(TypeHash::of::<T>())
     }
 
-    /// Finds a cycle in the graph starting from the specified index.
+    /// Gets iterator over target nodes with specified relation category.
     ///
     /// # Arguments
-    /// * `index` - The index to start searching for a cycle.
+    /// * `type_hash` - The type hash of the relation category.
+    pub fn outgoing_raw(&self, type_hash: TypeHash) -> impl Iterator<Item = AnyIndex> + 'a {
+        self.outgoing
+            .iter()
+            .find(|(category, _)| *category == type_hash)
+            .map(|(_, set)| *set)
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
+
+    /// Gets iterator over source nodes with specified relation category.
     ///
     /// # Type Parameters
     /// * `T` - The type of the relation category.
+    pub fn incoming<T>(&self) -> impl Iterator<Item = AnyIndex> + 'a {
+        self.incoming_raw(TypeHash::of::<T>())
+    }
+
+    /// Gets iterator over source nodes with specified relation category.
     ///
-    /// # Returns
-    /// A list of indices representing the cycle path.
-    pub fn find_cycle<T>(&self, index: AnyIndex) -> Vec<AnyIndex> {
-        fn walk<T>(
-            visited: &mut HashSet<AnyIndex>,
-            path: &mut Vec<AnyIndex>,
-            graph: &Graph,
-            source: AnyIndex,
-        ) -> Option<usize> {
-            if visited.contains(&source) {
-                return None;
-            }
-            visited.insert(source);
-            path.push(source);
-            for target in graph.relations_outgoing::<T>(source) {
-                if let Some(index) = path.iter().position(|item| *item == target) {
-                    return Some(index);
-                }
-                if let Some(index) = walk::<T>(visited, path, graph, target) {
-                    return Some(index);
-                }
-            }
-            path.pop();
-            None
-        }
+    /// # Arguments
+    /// * `type_hash` - The type hash of the relation category.
+    pub fn incoming_raw(&self, type_hash: TypeHash) -> impl Iterator<Item = AnyIndex> + 'a {
+        self.incoming
+            .iter()
+            .find(|(category, _)| *category == type_hash)
+            .map(|(_, set)| *set)
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
 
-        let mut visited = HashSet::default();
-        let mut path = Vec::default();
-        if let Some(index) = walk::<T>(&mut visited, &mut path, self, index) {
-            return path[index..].to_vec();
-        }
-        path
+    /// Gets iterator over relation categories of outgoing relations.
+    pub fn outgoing_categories(&self) -> impl Iterator<Item = TypeHash> + '_ {
+        self.outgoing.iter().map(|(category, _)| *category)
+    }
+
+    /// Gets iterator over relation categories of incoming relations.
+    pub fn incoming_categories(&self) -> impl Iterator<Item = TypeHash> + '_ {
+        self.incoming.iter().map(|(category, _)| *category)
+    }
+}
+
+/// Checker of single relation category edges, useful for many repeated
+/// relation checks.
+#[derive(Clone, Copy)]
+pub struct RelationChecker<'a> {
+    table: Option<&'a RelationsTable>,
+}
+
+impl RelationChecker<'_> {
+    /// Checks if two nodes are related.
+    ///
+    /// # Arguments
+    /// * `from` - The index of the source node.
+    /// * `to` - The index of the target node.
+    pub fn check(&self, from: AnyIndex, to: AnyIndex) -> bool {
+        self.table
+            .map(|table| table.contains(from, to))
+            .unwrap_or_default()
     }
 }
 
@@ -599,6 +3550,66 @@ impl Iterator for GraphTraverseIter<'_> {
     }
 }
 
+impl FusedIterator for GraphTraverseIter<'_> {}
+
+pub struct GraphTraverseDfsIter<'a> {
+    graph: &'a Graph,
+    stack: Vec<AnyIndex>,
+    visited: HashSet<AnyIndex>,
+    type_hash: TypeHash,
+}
+
+impl Iterator for GraphTraverseDfsIter<'_> {
+    type Item = AnyIndex;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.stack.pop() {
+            if !self.visited.insert(index) {
+                continue;
+            }
+            let start = self.stack.len();
+            self.stack
+                .extend(self.graph.relations_outgoing_raw(index, self.type_hash));
+            self.stack[start..].reverse();
+            return Some(index);
+        }
+        None
+    }
+}
+
+impl FusedIterator for GraphTraverseDfsIter<'_> {}
+
+pub struct GraphTraverseDepthIter<'a> {
+    graph: &'a Graph,
+    stack: VecDeque<(AnyIndex, usize)>,
+    visited: HashSet<AnyIndex>,
+    type_hash: TypeHash,
+    max_depth: usize,
+}
+
+impl Iterator for GraphTraverseDepthIter<'_> {
+    type Item = (usize, AnyIndex);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((index, depth)) = self.stack.pop_front() {
+            if !self.visited.insert(index) {
+                continue;
+            }
+            if depth < self.max_depth {
+                self.stack.extend(
+                    self.graph
+                        .relations_outgoing_raw(index, self.type_hash)
+                        .map(|index| (index, depth + 1)),
+                );
+            }
+            return Some((depth, index));
+        }
+        None
+    }
+}
+
+impl FusedIterator for GraphTraverseDepthIter<'_> {}
+
 pub struct GraphTraverseAnyIter<'a> {
     graph: &'a Graph,
     stack: VecDeque<AnyIndex>,
@@ -635,3 +3646,56 @@ impl Iterator for GraphTraverseAnyIter<'_> {
         None
     }
 }
+
+impl FusedIterator for GraphTraverseAnyIter<'_> {}
+
+pub struct GraphTraverseByKeyIter<'a, K: Ord, F: Fn(AnyIndex) -> K> {
+    graph: &'a Graph,
+    queue: BinaryHeap<Reverse<(K, AnyIndex)>>,
+    visited: HashSet<AnyIndex>,
+    type_hash: TypeHash,
+    key: F,
+}
+
+impl<'a, K: Ord, F: Fn(AnyIndex) -> K> GraphTraverseByKeyIter<'a, K, F> {
+    fn new(graph: &'a Graph, index: AnyIndex, type_hash: TypeHash, key: F) -> Self {
+        Self {
+            graph,
+            queue: [Reverse((key(index), index))].into(),
+            visited: Default::default(),
+            type_hash,
+            key,
+        }
+    }
+}
+
+impl<K: Ord, F: Fn(AnyIndex) -> K> Iterator for GraphTraverseByKeyIter<'_, K, F> {
+    type Item = AnyIndex;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Reverse((_, index))) = self.queue.pop() {
+            if self.visited.contains(&index) {
+                continue;
+            }
+            self.visited.insert(index);
+            for index in self.graph.relations_outgoing_raw(index, self.type_hash) {
+                if !self.visited.contains(&index) {
+                    self.queue.push(Reverse(((self.key)(index), index)));
+                }
+            }
+            return Some(index);
+        }
+        None
+    }
+}
+
+impl<K: Ord, F: Fn(AnyIndex) -> K> FusedIterator for GraphTraverseByKeyIter<'_, K, F> {}
+
+/// Record of relations removed from a node by `Graph::detach`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DetachedRelations {
+    /// Relation categories and target nodes of outgoing relations.
+    pub outgoing: Vec<(TypeHash, AnyIndex)>,
+    /// Relation categories and source nodes of incoming relations.
+    pub incoming: Vec<(TypeHash, AnyIndex)>,
+}
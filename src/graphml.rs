@@ -0,0 +1,247 @@
+use crate::{graph::Graph, utils::type_name};
+use intuicio_core::{registry::Registry, types::TypeQuery};
+use intuicio_data::type_hash::TypeHash;
+use intuicio_framework_arena::AnyIndex;
+use quick_xml::{
+    XmlVersion,
+    escape::{escape, resolve_predefined_entity},
+    events::{BytesRef, BytesStart, Event},
+    reader::Reader,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::Write,
+};
+
+fn find_type_hash(registry: &Registry, name: &str) -> Option<TypeHash> {
+    let (module_name, type_name) = match name.rsplit_once("::") {
+        Some((module_name, type_name)) => (Some(module_name), type_name),
+        None => (None, name),
+    };
+    registry
+        .find_type(TypeQuery {
+            name: Some(type_name.into()),
+            module_name: module_name.map(|name| name.into()),
+            ..Default::default()
+        })
+        .map(|type_| type_.type_hash())
+}
+
+fn attribute(tag: &BytesStart, name: &str) -> Result<Option<String>, Box<dyn Error>> {
+    for attribute in tag.attributes() {
+        let attribute = attribute?;
+        if attribute.key.as_ref() == name {
+            return Ok(Some(
+                attribute
+                    .normalized_value(XmlVersion::Implicit1_0)?
+                    .into_owned(),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+fn resolve_reference(reference: &BytesRef) -> Result<String, Box<dyn Error>> {
+    if let Some(character) = reference.resolve_char_ref()? {
+        return Ok(character.to_string());
+    }
+    resolve_predefined_entity(reference)
+        .map(|value| value.to_owned())
+        .ok_or_else(|| format!("Unknown GraphML entity: &{};", &**reference).into())
+}
+
+impl Graph {
+    /// Exports graph as GraphML document.
+    /// Each node gets its type name as `type` data, each relation becomes an
+    /// edge with its category type name as `category` data.
+    ///
+    /// # Arguments
+    /// * `registry` - The registry to use for type lookups.
+    /// * `node_attrs` - The function providing additional node attributes as
+    ///   pairs of name and value.
+    ///
+    /// # Returns
+    /// A string containing GraphML document.
+    pub fn to_graphml(
+        &self,
+        registry: &Registry,
+        node_attrs: impl Fn(AnyIndex) -> Vec<(String, String)>,
+    ) -> String {
+        let ids = self
+            .indices()
+            .enumerate()
+            .map(|(id, index)| (index, format!("n{id}")))
+            .collect::<HashMap<_, _>>();
+        let attributes = self
+            .indices()
+            .map(|index| (index, node_attrs(index)))
+            .collect::<Vec<_>>();
+        let names = attributes
+            .iter()
+            .flat_map(|(_, attributes)| attributes.iter().map(|(name, _)| name.as_str()))
+            .collect::<BTreeSet<_>>();
+        let mut result = String::default();
+        let _ = writeln!(result, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            result,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        );
+        let _ = writeln!(
+            result,
+            r#"  <key id="d0" for="node" attr.name="type" attr.type="string"/>"#
+        );
+        let _ = writeln!(
+            result,
+            r#"  <key id="d1" for="edge" attr.name="category" attr.type="string"/>"#
+        );
+        let keys = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (*name, format!("d{}", id + 2)))
+            .collect::<HashMap<_, _>>();
+        for name in &names {
+            let _ = writeln!(
+                result,
+                r#"  <key id="{}" for="node" attr.name="{}" attr.type="string"/>"#,
+                keys[name],
+                escape(*name)
+            );
+        }
+        let _ = writeln!(result, r#"  <graph id="G" edgedefault="directed">"#);
+        for (index, attributes) in &attributes {
+            let _ = writeln!(result, r#"    <node id="{}">"#, ids[index]);
+            let _ = writeln!(
+                result,
+                r#"      <data key="d0">{}</data>"#,
+                escape(type_name(registry, index.type_hash()))
+            );
+            for (name, value) in attributes {
+                let _ = writeln!(
+                    result,
+                    r#"      <data key="{}">{}</data>"#,
+                    keys[name.as_str()],
+                    escape(value)
+                );
+            }
+            let _ = writeln!(result, "    </node>");
+        }
+        for (id, (type_hash, from, to)) in self.relations().enumerate() {
+            let (Some(source), Some(target)) = (ids.get(&from), ids.get(&to)) else {
+                continue;
+            };
+            let _ = writeln!(
+                result,
+                r#"    <edge id="e{id}" source="{source}" target="{target}">"#
+            );
+            let _ = writeln!(
+                result,
+                r#"      <data key="d1">{}</data>"#,
+                escape(type_name(registry, type_hash))
+            );
+            let _ = writeln!(result, "    </edge>");
+        }
+        let _ = writeln!(result, "  </graph>");
+        let _ = writeln!(result, "</graphml>");
+        result
+    }
+
+    /// Imports graph topology from GraphML document.
+    /// Node data cannot be recovered from GraphML, so every node is inserted
+    /// as unit `()` node, while edges are related with categories found in
+    /// registry by edge data of the key named `category`. Keys are matched by
+    /// their `attr.name`, so documents written by other tools can be read as
+    /// long as their edges carry that data.
+    ///
+    /// # Arguments
+    /// * `source` - The GraphML document.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Returns
+    /// A result containing the graph and a mapping of GraphML node ids to
+    /// node indices.
+    pub fn from_graphml(
+        source: &str,
+        registry: &Registry,
+    ) -> Result<(Self, HashMap<String, AnyIndex>), Box<dyn Error>> {
+        let mut graph = Self::default();
+        let mut mappings = HashMap::<String, AnyIndex>::default();
+        let mut category_keys = Vec::<String>::default();
+        let mut edges = Vec::<(String, String, Option<String>)>::default();
+        let mut category = None::<String>;
+        let mut reader = Reader::from_str(source);
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) | Event::Empty(tag) => match tag.local_name().as_ref() {
+                    "key" => {
+                        let domain = attribute(&tag, "for")?;
+                        if attribute(&tag, "attr.name")?.as_deref() == Some("category")
+                            && matches!(domain.as_deref(), Some("edge" | "all"))
+                        {
+                            category_keys
+                                .push(attribute(&tag, "id")?.ok_or("GraphML key without id")?);
+                        }
+                    }
+                    "node" => {
+                        let id = attribute(&tag, "id")?.ok_or("GraphML node without id")?;
+                        mappings.insert(id, graph.insert(()));
+                    }
+                    "edge" => {
+                        let source =
+                            attribute(&tag, "source")?.ok_or("GraphML edge without source")?;
+                        let target =
+                            attribute(&tag, "target")?.ok_or("GraphML edge without target")?;
+                        edges.push((source, target, None));
+                    }
+                    "data" => {
+                        if let Some(key) = attribute(&tag, "key")?
+                            && category_keys.contains(&key)
+                            && let Some((_, _, None)) = edges.last()
+                        {
+                            category = Some(String::default());
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Text(text) => {
+                    if let Some(category) = category.as_mut() {
+                        category.push_str(&text.xml10_content());
+                    }
+                }
+                Event::CData(text) => {
+                    if let Some(category) = category.as_mut() {
+                        category.push_str(&text.xml10_content());
+                    }
+                }
+                Event::GeneralRef(reference) => {
+                    if let Some(category) = category.as_mut() {
+                        category.push_str(&resolve_reference(&reference)?);
+                    }
+                }
+                Event::End(tag) => {
+                    if tag.local_name().as_ref() == "data"
+                        && let Some(category) = category.take()
+                        && let Some((_, _, edge_category)) = edges.last_mut()
+                    {
+                        *edge_category = Some(category.trim().to_owned());
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        for (source, target, category) in edges {
+            let category = category.ok_or("GraphML edge without category")?;
+            let type_hash = find_type_hash(registry, &category)
+                .ok_or_else(|| format!("Could not find relation category: {category}"))?;
+            let from = *mappings
+                .get(&source)
+                .ok_or_else(|| format!("Could not find GraphML node: {source}"))?;
+            let to = *mappings
+                .get(&target)
+                .ok_or_else(|| format!("Could not find GraphML node: {target}"))?;
+            graph.relate_raw(from, to, type_hash);
+        }
+        Ok((graph, mappings))
+    }
+}
@@ -1,31 +1,98 @@
+pub mod command;
+pub mod external;
+pub mod frozen;
 pub mod graph;
 pub mod prefab;
 pub mod query;
+pub mod report;
 
+mod dot;
+#[cfg(feature = "graphml")]
+mod graphml;
+#[cfg(feature = "petgraph")]
+mod petgraph_bridge;
 mod relations;
+mod utils;
 
 pub use intuicio_data::lifetime::{ValueReadAccess, ValueWriteAccess};
 pub use intuicio_framework_arena::AnyIndex;
 
+/// Commonly used types, so a single glob import covers typical usage.
+///
+/// ```
+/// use nodio::prelude::*;
+///
+/// struct Child;
+/// struct Health(usize);
+///
+/// let mut graph = Graph::default();
+/// let root = graph.insert(());
+/// let health = graph.insert(Health(10));
+/// graph.relate::<Child>(root, health);
+///
+/// for mut health in graph.query::<Related<Child, &mut Health>>(root) {
+///     health.0 -= 1;
+/// }
+/// let total = graph
+///     .query::<Related<Child, &Health>>(root)
+///     .map(|health| health.0)
+///     .sum::<usize>();
+/// assert_eq!(total, 9);
+/// ```
+pub mod prelude {
+    pub use crate::{
+        AnyIndex, ValueReadAccess, ValueWriteAccess,
+        command::GraphCommand,
+        external::ExternalRelations,
+        frozen::FrozenGraph,
+        graph::{CardinalityPolicy, DetachedRelations, Graph},
+        prefab::Prefab,
+        query::{
+            Cloned, Copied, Count, DegreeAtLeast, DistinctByIndex, Is, IsNot, Limit, Node, Not, Or,
+            Query, QueryFetch, QueryIter, QueryPredicate, QueryTransform, ReadWhere, Related,
+            RelatedIncoming, Single, Tagged, Traverse, TraverseDepth, TraverseDfs,
+            TraverseTopological, TryTraverseTopological, Untagged, WithSiblingCount,
+        },
+    };
+}
+
 pub mod third_party {
     pub use intuicio_core;
     pub use intuicio_data;
     pub use intuicio_derive;
     pub use intuicio_framework_arena;
     pub use intuicio_framework_serde;
+    #[cfg(feature = "petgraph")]
+    pub use petgraph;
+    #[cfg(feature = "rand")]
+    pub use rand;
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        graph::Graph,
-        prefab::Prefab,
-        query::{Is, Node, Query, Related, Traverse},
+        command::GraphCommand,
+        external::ExternalRelations,
+        graph::{CardinalityPolicy, Graph, RelationError},
+        prefab::{Prefab, PrefabDataType, PrefabError, PrefabRelationsPairItem},
+        query::{
+            Count, DegreeAtLeast, DistinctByIndex, Is, Node, Not, Or, Query, QueryPredicate,
+            ReadWhere, Related, RelatedIncoming, Tagged, Traverse, TraverseDepth, TraverseDfs,
+            TraverseTopological, TryTraverseTopological, Untagged, WithSiblingCount,
+        },
+    };
+    use intuicio_core::{
+        registry::Registry,
+        types::{TypeQuery, struct_type::NativeStructBuilder},
     };
-    use intuicio_core::{registry::Registry, types::struct_type::NativeStructBuilder};
+    use intuicio_data::type_hash::TypeHash;
     use intuicio_framework_arena::AnyIndex;
-    use intuicio_framework_serde::SerializationRegistry;
+    use intuicio_framework_serde::{Intermediate, SerializationRegistry};
     use serde::{Deserialize, Serialize};
+    use std::{
+        collections::{HashMap, HashSet},
+        iter::FusedIterator,
+    };
 
     fn is_async<T: Send + Sync>() {}
 
@@ -55,7 +122,7 @@ mod tests {
         forward: bool,
     }
 
-    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
     struct Position(i32, i32);
 
     #[derive(Debug, Default, Serialize, Deserialize)]
@@ -186,4 +253,3405 @@ mod tests {
         graph.relate::<()>(d, a);
         assert!(graph.find_cycles::<()>().next().is_some());
     }
+
+    #[test]
+    fn test_distinct_by_index() {
+        let mut graph = Graph::default();
+        let a = graph.insert(0usize);
+        let b = graph.insert(1usize);
+        let c = graph.insert(2usize);
+        let d = graph.insert(3usize);
+
+        graph.relate::<()>(a, b);
+        graph.relate::<()>(a, c);
+        graph.relate::<()>(b, d);
+        graph.relate::<()>(c, d);
+
+        let duplicated = graph
+            .query::<Traverse<(), Query<AnyIndex, Related<(), &usize>>>>(a)
+            .filter(|value| **value == 3)
+            .count();
+        assert_eq!(duplicated, 2);
+
+        let distinct = graph
+            .query::<DistinctByIndex<Traverse<(), Query<AnyIndex, Related<(), AnyIndex>>>, &usize>>(
+                a,
+            )
+            .map(|value| *value)
+            .collect::<Vec<_>>();
+        assert_eq!(distinct.iter().filter(|value| **value == 3).count(), 1);
+        assert_eq!(distinct.len(), 3);
+    }
+
+    #[test]
+    fn test_for_each_mut() {
+        let mut graph = Graph::default();
+        let trees = (0..3).map(|_| graph.insert(Tree)).collect::<Vec<_>>();
+
+        graph.for_each_mut::<Tree>(|index, graph| {
+            let fire = graph.insert(Fire);
+            graph.relate::<Effect>(index, fire);
+        });
+
+        for tree in trees {
+            assert_eq!(graph.find::<Effect, Fire>(tree).count(), 1);
+        }
+        assert_eq!(graph.iter::<Fire>().count(), 3);
+    }
+
+    #[test]
+    fn test_relation_table_view() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(Tree);
+        let b = graph.insert(Tree);
+        graph.relate_pair::<Parent, Child>(root, a);
+        graph.relate_pair::<Parent, Child>(root, b);
+
+        assert!(graph.relation_table_view::<Effect>().is_none());
+
+        let view = graph.relation_table_view::<Parent>().unwrap();
+        let mut edges = view.iter().collect::<Vec<_>>();
+        edges.sort();
+        let mut expected = vec![(a, root), (b, root)];
+        expected.sort();
+        assert_eq!(edges, expected);
+        assert!(view.contains(a, root));
+        assert!(!view.contains(root, a));
+        assert_eq!(view.outgoing(a).collect::<Vec<_>>(), vec![root]);
+        assert_eq!(view.incoming(root).count(), 2);
+    }
+
+    #[test]
+    fn test_read_where() {
+        struct Alive;
+
+        impl QueryPredicate<Health> for Alive {
+            fn test(value: &Health) -> bool {
+                value.0 > 0
+            }
+        }
+
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        for index in 0..4 {
+            let health = graph.insert(Health(index % 2));
+            graph.relate::<Child>(root, health);
+        }
+
+        let alive = graph
+            .query::<Related<Child, ReadWhere<Health, Alive>>>(root)
+            .map(|health| health.0)
+            .collect::<Vec<_>>();
+        assert_eq!(alive, vec![1, 1]);
+    }
+
+    #[test]
+    fn test_traverse_by_key() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Position(0, 0));
+        let c = graph.insert(Position(3, 0));
+        let a = graph.insert(Position(1, 0));
+        let b = graph.insert(Position(2, 0));
+        let d = graph.insert(Position(4, 0));
+        graph.relate::<Child>(root, c);
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, d);
+
+        let order = graph
+            .relations_traverse_by_key::<Child, _>(root, |index| {
+                graph
+                    .read::<Position>(index)
+                    .map(|position| position.0)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![root, a, b, c, d]);
+    }
+
+    #[test]
+    fn test_retain_relations() {
+        let mut graph = Graph::default();
+        let fire = graph.insert(Fire);
+        let trees = (0..6).map(|_| graph.insert(Tree)).collect::<Vec<_>>();
+        for tree in &trees {
+            graph.relate::<Effect>(*tree, fire);
+            graph.relate::<Attribute>(*tree, fire);
+        }
+
+        graph.retain_relations::<Effect>(|from, _| {
+            trees.iter().position(|tree| *tree == from).unwrap() % 2 != 0
+        });
+
+        for (index, tree) in trees.iter().enumerate() {
+            assert_eq!(graph.are_related::<Effect>(*tree, fire), index % 2 != 0);
+            assert!(graph.are_related::<Attribute>(*tree, fire));
+        }
+        assert_eq!(graph.relations_incoming::<Effect>(fire).count(), 3);
+    }
+
+    #[test]
+    fn test_query_all_with_root() {
+        let mut graph = Graph::default();
+        let trees = (0..3)
+            .map(|index| {
+                let tree = graph.insert(Tree);
+                for _ in 0..=index {
+                    let health = graph.insert(Health(2));
+                    graph.relate::<Child>(tree, health);
+                }
+                tree
+            })
+            .collect::<Vec<_>>();
+
+        let mut sums = HashMap::<AnyIndex, usize>::default();
+        for (tree, health) in graph.query_all_with_root::<Tree, Related<Child, &Health>>() {
+            *sums.entry(tree).or_default() += health.0;
+        }
+        assert_eq!(sums.len(), 3);
+        for (index, tree) in trees.iter().enumerate() {
+            assert_eq!(sums[tree], (index + 1) * 2);
+        }
+    }
+
+    #[test]
+    fn test_external_relations() {
+        let mut graph_a = Graph::default();
+        let mut graph_b = Graph::default();
+        let a = graph_a.insert(Player);
+        let b = graph_b.insert(Tree);
+
+        let mut external = ExternalRelations::default();
+        external.relate_external::<Effect>((0, a), (1, b));
+
+        assert!(external.are_related_external::<Effect>((0, a), (1, b)));
+        assert!(!external.are_related_external::<Effect>((1, b), (0, a)));
+        assert!(!external.are_related_external::<Child>((0, a), (1, b)));
+        assert_eq!(
+            external
+                .relations_outgoing_external::<Effect>((0, a))
+                .collect::<Vec<_>>(),
+            vec![(1, b)]
+        );
+        assert_eq!(
+            external
+                .relations_incoming_external::<Effect>((1, b))
+                .collect::<Vec<_>>(),
+            vec![(0, a)]
+        );
+        for (id, index) in external.relations_outgoing_external::<Effect>((0, a)) {
+            assert_eq!(id, 1);
+            assert!(graph_b.is::<Tree>(index));
+        }
+
+        external.remove_graph(1);
+        assert!(!external.are_related_external::<Effect>((0, a), (1, b)));
+        assert_eq!(external, ExternalRelations::default());
+
+        external.relate_external::<Effect>((0, a), (1, b));
+        external.relate_external::<Child>((0, a), (0, a));
+        external.unrelate_external::<Effect>((0, a), (1, b));
+        external.unrelate_external::<Child>((0, a), (0, a));
+        assert_eq!(external, ExternalRelations::default());
+    }
+
+    #[test]
+    fn test_relation_types() {
+        let mut graph = Graph::default();
+        let fire = graph.insert(Fire);
+        let player = graph.insert(Player);
+        for _ in 0..3 {
+            let tree = graph.insert(Tree);
+            graph.relate::<Effect>(tree, fire);
+        }
+        graph.relate::<Effect>(player, fire);
+
+        assert_eq!(
+            graph.target_types::<Effect>(),
+            [TypeHash::of::<Fire>()].into()
+        );
+        assert_eq!(
+            graph.source_types::<Effect>(),
+            [TypeHash::of::<Tree>(), TypeHash::of::<Player>()].into()
+        );
+        assert!(graph.target_types::<Child>().is_empty());
+    }
+
+    #[test]
+    fn test_query_limited() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        for _ in 0..5 {
+            let tree = graph.insert(Tree);
+            graph.relate::<Child>(root, tree);
+        }
+
+        assert_eq!(
+            graph
+                .query_limited::<Related<Child, AnyIndex>>(root, 2)
+                .count(),
+            2
+        );
+        assert_eq!(
+            graph
+                .query_limited::<Related<Child, AnyIndex>>(root, 10)
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_incoming_spelling() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, c);
+        graph.relate::<Parent>(c, a);
+
+        let sorted = |iter: &mut dyn Iterator<Item = AnyIndex>| {
+            let mut result = iter.collect::<Vec<_>>();
+            result.sort();
+            result
+        };
+        assert_eq!(
+            sorted(&mut graph.relations_incoming::<Child>(c)),
+            sorted(&mut graph.relations_incomming::<Child>(c))
+        );
+        assert_eq!(
+            sorted(&mut graph.relations_incoming_raw(c, TypeHash::of::<Child>())),
+            sorted(&mut graph.relations_incomming_raw(c, TypeHash::of::<Child>()))
+        );
+        assert_eq!(
+            sorted(&mut graph.relations_incoming_any(a)),
+            sorted(&mut graph.relations_incomming_any(a))
+        );
+        assert_eq!(
+            graph.relations_incoming_all::<Child>().count(),
+            graph.relations_incomming_all::<Child>().count()
+        );
+        assert_eq!(
+            graph
+                .relations_incoming_all_raw(TypeHash::of::<Parent>())
+                .collect::<Vec<_>>(),
+            graph
+                .relations_incomming_all_raw(TypeHash::of::<Parent>())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_outgoing_degree_matrix() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let player = graph.insert(Player);
+        graph.relate_pair::<Parent, Child>(root, player);
+        let name = graph.insert("Player".to_owned());
+        graph.relate::<Attribute>(player, name);
+        let controller = graph.insert(Controller { forward: true });
+        graph.relate_pair::<Parent, Child>(player, controller);
+        let position = graph.insert(Position(0, 0));
+        graph.relate_pair::<Parent, Child>(player, position);
+        graph.unrelate::<Attribute>(player, name);
+        graph.relate::<Attribute>(player, name);
+
+        let matrix = graph.outgoing_degree_matrix();
+        let player_degrees = &matrix[&player];
+        assert_eq!(player_degrees[&TypeHash::of::<Child>()], 2);
+        assert_eq!(player_degrees[&TypeHash::of::<Attribute>()], 1);
+        assert_eq!(player_degrees[&TypeHash::of::<Parent>()], 1);
+        assert_eq!(player_degrees.len(), 3);
+        assert_eq!(matrix[&root].len(), 1);
+        assert!(!matrix.contains_key(&name));
+    }
+
+    #[test]
+    fn test_relate_if_present() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.remove(c).unwrap();
+
+        assert!(graph.relate_if_present::<Child>(a, b));
+        assert!(graph.are_related::<Child>(a, b));
+        assert!(!graph.relate_if_present::<Child>(a, c));
+        assert!(!graph.relate_if_present::<Child>(c, a));
+        assert!(!graph.are_related::<Child>(a, c));
+        assert!(!graph.are_related::<Child>(c, a));
+    }
+
+    #[cfg(feature = "graphml")]
+    #[test]
+    fn test_graphml() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let player = graph.insert(Player);
+        graph.relate_pair::<Parent, Child>(root, player);
+        for _ in 0..3 {
+            let tree = graph.insert(Tree);
+            graph.relate_pair::<Parent, Child>(root, tree);
+            graph.relate::<Effect>(player, tree);
+        }
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Parent>().build())
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Effect>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Tree>().build());
+
+        let graphml = graph.to_graphml(&registry, |index| {
+            vec![("display name".to_owned(), format!("<{index}>"))]
+        });
+        assert!(graphml.contains(r#"<key id="d2" for="node" attr.name="display name""#));
+        assert!(graphml.contains(r#"<data key="d2">&lt;"#));
+
+        let (graph2, mappings) = Graph::from_graphml(&graphml, &registry).unwrap();
+        assert_eq!(mappings.len(), graph.indices().count());
+        assert_eq!(graph2.indices().count(), graph.indices().count());
+        assert_eq!(graph2.relations().count(), graph.relations().count());
+        assert_eq!(
+            graph2.relations_outgoing_all::<Child>().count(),
+            graph.relations_outgoing_all::<Child>().count()
+        );
+        assert_eq!(
+            graph2.relations_outgoing_all::<Effect>().count(),
+            graph.relations_outgoing_all::<Effect>().count()
+        );
+        assert_eq!(graph2.generation(), graph.relations().count() as u64);
+
+        let graphml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="node" attr.name="label" attr.type="string"/>
+  <key id="d5"
+       for="edge"
+       attr.name="category" attr.type="string"/>
+  <graph id="G" edgedefault="directed">
+    <!-- nodes -> edges -->
+    <node
+      id="a"><data key="d0">A &amp; B</data></node>
+    <node id="b"/>
+    <edge source="a"
+          target="b"><data key="d5"><![CDATA[Effect]]></data></edge>
+    <edge source="b" target="a">
+      <data key="d5">Child</data>
+    </edge>
+  </graph>
+</graphml>"#;
+        let (graph3, mappings) = Graph::from_graphml(graphml, &registry).unwrap();
+        assert_eq!(mappings.len(), 2);
+        assert!(graph3.are_related::<Effect>(mappings["a"], mappings["b"]));
+        assert!(graph3.are_related::<Child>(mappings["b"], mappings["a"]));
+        assert_eq!(graph3.relations().count(), 2);
+        assert!(Graph::from_graphml("<graphml><node id=\"a\"", &registry).is_err());
+    }
+
+    #[test]
+    fn test_first_common_descendant() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+        let e = graph.insert(());
+        let f = graph.insert(());
+        let g = graph.insert(());
+
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, d);
+        graph.relate::<Child>(c, e);
+        graph.relate::<Child>(d, e);
+        graph.relate::<Child>(e, f);
+
+        assert_eq!(graph.first_common_descendant::<Child>(a, b), Some(e));
+        assert_eq!(graph.first_common_descendant::<Child>(a, c), Some(c));
+        assert_eq!(graph.first_common_descendant::<Child>(a, a), Some(a));
+        assert_eq!(graph.first_common_descendant::<Child>(a, g), None);
+        assert_eq!(graph.first_common_descendant::<Parent>(a, b), None);
+    }
+
+    #[test]
+    fn test_kahn_layers() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+
+        graph.relate::<()>(a, b);
+        graph.relate::<()>(a, c);
+        graph.relate::<()>(b, d);
+        graph.relate::<()>(c, d);
+
+        let mut layers = graph.kahn_layers::<()>().unwrap();
+        assert_eq!(layers.len(), 3);
+        layers[1].sort();
+        let mut middle = vec![b, c];
+        middle.sort();
+        assert_eq!(layers[0], vec![a]);
+        assert_eq!(layers[1], middle);
+        assert_eq!(layers[2], vec![d]);
+
+        graph.relate::<()>(d, a);
+        let cycle = graph.kahn_layers::<()>().unwrap_err();
+        assert_eq!(cycle.len(), 3);
+        assert!(cycle.contains(&a) && cycle.contains(&d));
+    }
+
+    #[test]
+    fn test_relation_checker() {
+        let mut graph = Graph::default();
+        let nodes = (0..10).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for pair in nodes.windows(2) {
+            graph.relate::<Child>(pair[0], pair[1]);
+        }
+
+        let checker = graph.relation_checker::<Child>();
+        let missing = graph.relation_checker::<Parent>();
+        for from in &nodes {
+            for to in &nodes {
+                assert_eq!(
+                    checker.check(*from, *to),
+                    graph.are_related::<Child>(*from, *to)
+                );
+                assert!(!missing.check(*from, *to));
+            }
+        }
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Parent>().build())
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Tree>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Parent>()
+            .with_serde::<Child>()
+            .with_serde::<Tree>()
+            .with_serde::<Health>();
+
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        for index in 0..3 {
+            let tree = graph.insert(Tree);
+            graph.relate_pair::<Parent, Child>(root, tree);
+            let health = graph.insert(Health(index));
+            graph.relate_pair::<Parent, Child>(tree, health);
+        }
+
+        let mut graph2 = Graph::default();
+        let healths = (0..3).rev().map(|index| graph2.insert(Health(index)));
+        let healths = healths.collect::<Vec<_>>();
+        let trees = (0..3).map(|_| graph2.insert(Tree)).collect::<Vec<_>>();
+        let root = graph2.insert(());
+        for (tree, health) in trees.iter().zip(healths.iter()) {
+            graph2.relate_pair::<Parent, Child>(*tree, *health);
+            graph2.relate_pair::<Parent, Child>(root, *tree);
+        }
+
+        let hash = graph.content_hash(&serialization, &registry).unwrap();
+        let hash2 = graph2.content_hash(&serialization, &registry).unwrap();
+        assert_eq!(hash, hash2);
+        // Hash is stable across builds and platforms, so it can be pinned.
+        assert_eq!(hash, 16219918189046991781);
+
+        graph2.unrelate_pair::<Parent, Child>(root, trees[0]);
+        let hash3 = graph2.content_hash(&serialization, &registry).unwrap();
+        assert_ne!(hash, hash3);
+
+        let health = graph.iter::<Health>().next().unwrap().0;
+        *graph.write::<Health>(health).unwrap() = Health(42);
+        let hash4 = graph.content_hash(&serialization, &registry).unwrap();
+        assert_ne!(hash, hash4);
+    }
+
+    #[test]
+    fn test_degree_at_least() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let player = graph.insert(Player);
+        graph.relate_pair::<Parent, Child>(root, player);
+        let controller = graph.insert(Controller { forward: true });
+        graph.relate_pair::<Parent, Child>(player, controller);
+        let position = graph.insert(Position(0, 0));
+        graph.relate_pair::<Parent, Child>(player, position);
+        let fire = graph.insert(Fire);
+        for index in 0..3 {
+            let tree = graph.insert(Tree);
+            graph.relate_pair::<Parent, Child>(root, tree);
+            let health = graph.insert(Health(2));
+            graph.relate_pair::<Parent, Child>(tree, health);
+            if index > 0 {
+                graph.relate::<Effect>(health, fire);
+            }
+        }
+
+        let mut matches = graph
+            .query::<Traverse<Child, Query<AnyIndex, (AnyIndex, DegreeAtLeast<Child, 2>)>>>(root)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        matches.sort();
+        let mut expected = vec![root, player];
+        expected.sort();
+        assert_eq!(matches, expected);
+
+        assert_eq!(
+            graph
+                .query::<Traverse<Child, Query<AnyIndex, Related<Child, DegreeAtLeast<Effect, 1>>>>>(root)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_relations_since() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Child>(a, b);
+        graph.relate::<Parent>(b, a);
+
+        let generation = graph.generation();
+        assert!(graph.relations_since(generation).next().is_none());
+
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(a, b);
+        graph.relate::<Effect>(c, b);
+        let mut added = graph.relations_since(generation).collect::<Vec<_>>();
+        added.sort();
+        let mut expected = vec![
+            (TypeHash::of::<Child>(), a, c),
+            (TypeHash::of::<Effect>(), c, b),
+        ];
+        expected.sort();
+        assert_eq!(added, expected);
+        assert_eq!(graph.relations_since(0).count(), 4);
+
+        graph.unrelate::<Child>(a, c);
+        assert_eq!(graph.relations_since(generation).count(), 1);
+    }
+
+    #[test]
+    fn test_tags() {
+        struct Selected;
+
+        let mut graph = Graph::default();
+        let trees = (0..5).map(|_| graph.insert(Tree)).collect::<Vec<_>>();
+        graph.add_tag::<Selected>(trees[1]);
+        graph.add_tag::<Selected>(trees[3]);
+
+        let mut tagged = graph.tagged::<Selected>().collect::<Vec<_>>();
+        tagged.sort();
+        let mut expected = vec![trees[1], trees[3]];
+        expected.sort();
+        assert_eq!(tagged, expected);
+        assert!(graph.has_tag::<Selected>(trees[1]));
+        assert!(!graph.has_tag::<Selected>(trees[0]));
+        assert!(!graph.has_tag::<Fire>(trees[1]));
+
+        graph.remove_tag::<Selected>(trees[1]);
+        assert!(!graph.has_tag::<Selected>(trees[1]));
+        graph.remove(trees[3]).unwrap();
+        assert!(graph.tagged::<Selected>().next().is_none());
+    }
+
+    #[test]
+    fn test_query_tagged() {
+        struct Selected;
+
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let children = (0..4)
+            .map(|index| {
+                let child = graph.insert(Position(index, 0));
+                graph.relate::<Child>(root, child);
+                child
+            })
+            .collect::<Vec<_>>();
+        graph.add_tag::<Selected>(children[0]);
+        graph.add_tag::<Selected>(children[2]);
+
+        let mut selected = graph
+            .query::<Related<Child, Query<AnyIndex, (Tagged<Selected>, &Position)>>>(root)
+            .map(|(_, position)| position.0)
+            .collect::<Vec<_>>();
+        selected.sort();
+        assert_eq!(selected, vec![0, 2]);
+
+        let mut unselected = graph
+            .query::<Related<Child, Query<AnyIndex, (Untagged<Selected>, &Position)>>>(root)
+            .map(|(_, position)| position.0)
+            .collect::<Vec<_>>();
+        unselected.sort();
+        assert_eq!(unselected, vec![1, 3]);
+
+        assert_eq!(
+            graph
+                .query::<Related<Child, Tagged<Selected>>>(root)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_prepare_types() {
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Tree>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build());
+        let types = [TypeHash::of::<Tree>(), TypeHash::of::<Player>()]
+            .into_iter()
+            .map(|type_hash| {
+                registry
+                    .find_type(TypeQuery {
+                        type_hash: Some(type_hash),
+                        ..Default::default()
+                    })
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let types = types.iter().map(|type_| &**type_).collect::<Vec<_>>();
+
+        let mut prepared = Graph::default().with_new_arena_capacity(64);
+        prepared.prepare_types(&types, 4096);
+        assert!(prepared.is_empty());
+        assert_eq!(prepared.nodes.arenas().len(), 2);
+        assert_eq!(prepared.node_capacity::<Tree>(), 4096);
+        assert_eq!(prepared.node_capacity::<Player>(), 4096);
+        prepared.prepare_types(&types, 8);
+        assert_eq!(prepared.nodes.arenas().len(), 2);
+        assert_eq!(prepared.node_capacity::<Tree>(), 4096);
+        let fire = prepared.insert(Fire);
+        assert_eq!(prepared.node_capacity::<Fire>(), 64);
+        prepared.remove(fire).unwrap();
+
+        let mut unprepared = Graph::default().with_new_arena_capacity(64);
+        for graph in [&mut prepared, &mut unprepared] {
+            let tree = graph.insert(Tree);
+            let player = graph.insert(Player);
+            assert!(graph.is::<Tree>(tree));
+            assert!(graph.is::<Player>(player));
+        }
+        assert_eq!(prepared.len(), unprepared.len());
+        assert_eq!(prepared.nodes.arenas().len(), 3);
+    }
+
+    #[test]
+    fn test_swap_relation_categories() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Parent>(a, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, c);
+
+        graph.swap_relation_categories::<Parent, Child>();
+        assert_eq!(
+            graph.relations_outgoing::<Parent>(a).collect::<Vec<_>>(),
+            vec![c]
+        );
+        assert_eq!(
+            graph.relations_outgoing::<Parent>(b).collect::<Vec<_>>(),
+            vec![c]
+        );
+        assert_eq!(
+            graph.relations_outgoing::<Child>(a).collect::<Vec<_>>(),
+            vec![b]
+        );
+        assert!(graph.relations_outgoing::<Child>(b).next().is_none());
+
+        graph.swap_relation_categories::<Child, Effect>();
+        assert!(graph.relations_outgoing::<Child>(a).next().is_none());
+        assert_eq!(
+            graph.relations_outgoing::<Effect>(a).collect::<Vec<_>>(),
+            vec![b]
+        );
+    }
+
+    #[test]
+    fn test_descendant_counts() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let a1 = graph.insert(());
+        let a2 = graph.insert(());
+        let a21 = graph.insert(());
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, a1);
+        graph.relate::<Child>(a, a2);
+        graph.relate::<Child>(a2, a21);
+
+        assert_eq!(graph.descendant_count::<Child>(root), 5);
+        assert_eq!(graph.descendant_count::<Child>(a), 3);
+        assert_eq!(graph.descendant_count::<Child>(a2), 1);
+        assert_eq!(graph.descendant_count::<Child>(b), 0);
+
+        let counts = graph.descendant_counts::<Child>();
+        assert_eq!(counts.len(), 6);
+        for (index, expected) in [(root, 5), (a, 3), (b, 0), (a1, 0), (a2, 1), (a21, 0)] {
+            assert_eq!(counts[&index], expected);
+            assert_eq!(graph.descendant_count::<Child>(index), expected);
+        }
+
+        graph.relate::<Child>(a21, a);
+        let counts = graph.descendant_counts::<Child>();
+        assert_eq!(counts[&a], 3);
+        assert_eq!(counts[&a21], 3);
+        assert_eq!(counts[&root], 5);
+    }
+
+    #[test]
+    fn test_fused_iterators() {
+        fn assert_fused(iter: impl FusedIterator) {
+            let mut iter = iter;
+            while iter.next().is_some() {}
+            for _ in 0..3 {
+                assert!(iter.next().is_none());
+            }
+        }
+
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(Position(1, 2));
+        let b = graph.insert(Position(3, 4));
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(a, b);
+        graph.relate::<Effect>(root, b);
+
+        assert_fused(graph.relations_traverse::<Child>(root));
+        assert_fused(graph.relations_traverse_any(root));
+        assert_fused(graph.relations_traverse_by_key::<Child, _>(root, |index| index));
+        assert_fused(graph.query::<Related<Child, &Position>>(root));
+        assert_fused(graph.query::<(Related<Child, AnyIndex>, Related<Effect, AnyIndex>)>(root));
+    }
+
+    #[test]
+    fn test_read_dynamic() {
+        let mut graph = Graph::default();
+        let index = graph.insert(Position(4, -2));
+
+        let (pointer, layout) = unsafe { graph.read_dynamic(index).unwrap() };
+        assert_eq!(layout.size(), std::mem::size_of::<Position>());
+        let mut value = std::mem::MaybeUninit::<Position>::uninit();
+        let value = unsafe {
+            std::ptr::copy_nonoverlapping(pointer, value.as_mut_ptr().cast::<u8>(), layout.size());
+            value.assume_init()
+        };
+        assert_eq!(value.0, 4);
+        assert_eq!(value.1, -2);
+
+        graph.remove(index).unwrap();
+        assert!(unsafe { graph.read_dynamic(index) }.is_err());
+        assert!(
+            unsafe { graph.read_dynamic(AnyIndex::new(index.index(), TypeHash::of::<Health>())) }
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_relation_difference() {
+        fn build() -> (Graph, Vec<AnyIndex>) {
+            let mut graph = Graph::default();
+            let nodes = (0..4).map(|_| graph.insert(())).collect::<Vec<_>>();
+            graph.relate::<Child>(nodes[0], nodes[1]);
+            graph.relate::<Child>(nodes[0], nodes[2]);
+            graph.relate::<Effect>(nodes[2], nodes[3]);
+            (graph, nodes)
+        }
+
+        let (graph, nodes) = build();
+        let (mut other, other_nodes) = build();
+        assert_eq!(nodes, other_nodes);
+        assert_eq!(graph.relation_difference(&other), (vec![], vec![]));
+
+        other.relate::<Effect>(nodes[1], nodes[3]);
+        let (removed, added) = graph.relation_difference(&other);
+        assert!(removed.is_empty());
+        assert_eq!(added, vec![(TypeHash::of::<Effect>(), nodes[1], nodes[3])]);
+
+        other.unrelate::<Child>(nodes[0], nodes[2]);
+        let (removed, added) = graph.relation_difference(&other);
+        assert_eq!(removed, vec![(TypeHash::of::<Child>(), nodes[0], nodes[2])]);
+        assert_eq!(added.len(), 1);
+    }
+
+    #[test]
+    fn test_traverse_topological() {
+        let mut graph = Graph::default();
+        let a = graph.insert(Health(0));
+        let b = graph.insert(Health(1));
+        let c = graph.insert(Health(2));
+        let d = graph.insert(Health(3));
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(c, b);
+        graph.relate::<Child>(b, d);
+        graph.relate::<Child>(c, d);
+
+        let order = graph
+            .query::<TraverseTopological<Child, AnyIndex>>(a)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![a, c, b, d]);
+        let values = graph
+            .query::<TraverseTopological<Child, &Health>>(a)
+            .map(|health| health.0)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![0, 2, 1, 3]);
+        let order = graph
+            .query::<TryTraverseTopological<Child, AnyIndex>>(a)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![Ok(a), Ok(c), Ok(b), Ok(d)]);
+        assert_eq!(
+            graph.relations_traverse_topological::<Child>(c),
+            Ok(vec![c, b, d])
+        );
+
+        graph.relate::<Child>(d, c);
+        assert!(graph.relations_traverse_topological::<Child>(a).is_err());
+        let order = graph
+            .query::<TryTraverseTopological<Child, AnyIndex>>(a)
+            .collect::<Vec<_>>();
+        assert_eq!(order.len(), 4);
+        assert!(order.iter().all(|index| index.is_err()));
+        assert_eq!(
+            graph
+                .query::<TraverseTopological<Child, AnyIndex>>(a)
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_bridges() {
+        let mut graph = Graph::default();
+        let nodes = (0..6).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate::<Child>(nodes[0], nodes[1]);
+        graph.relate::<Child>(nodes[1], nodes[2]);
+        graph.relate::<Child>(nodes[2], nodes[0]);
+        graph.relate::<Child>(nodes[3], nodes[4]);
+        graph.relate::<Child>(nodes[4], nodes[5]);
+        graph.relate::<Child>(nodes[5], nodes[3]);
+        graph.relate::<Child>(nodes[3], nodes[2]);
+
+        assert_eq!(graph.bridges::<Child>(), vec![(nodes[3], nodes[2])]);
+        assert!(graph.bridges::<Effect>().is_empty());
+
+        graph.relate::<Child>(nodes[1], nodes[4]);
+        assert!(graph.bridges::<Child>().is_empty());
+    }
+
+    #[test]
+    fn test_articulation_points() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(b, c);
+        assert_eq!(graph.articulation_points::<Child>(), HashSet::from([b]));
+
+        graph.relate::<Child>(c, a);
+        assert!(graph.articulation_points::<Child>().is_empty());
+
+        let d = graph.insert(());
+        let e = graph.insert(());
+        graph.relate::<Child>(d, a);
+        graph.relate::<Child>(a, e);
+        assert_eq!(graph.articulation_points::<Child>(), HashSet::from([a]));
+    }
+
+    #[test]
+    fn test_pagerank() {
+        let mut graph = Graph::default();
+        let hub = graph.insert(());
+        let others = (0..4).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for other in &others {
+            graph.relate::<Child>(*other, hub);
+        }
+        graph.relate::<Child>(hub, others[0]);
+        graph.relate::<Child>(others[1], others[2]);
+
+        let ranks = graph.pagerank::<Child>(0.85, 50);
+        assert_eq!(ranks.len(), 5);
+        assert!((ranks.values().sum::<f64>() - 1.0).abs() < 1.0e-9);
+        for other in &others {
+            assert!(ranks[&hub] > ranks[other]);
+        }
+        assert!(ranks[&others[0]] > ranks[&others[3]]);
+        assert!(graph.pagerank::<Effect>(0.85, 50).is_empty());
+    }
+
+    #[test]
+    fn test_frozen_graph() {
+        let mut graph = Graph::default();
+        let nodes = (0..200)
+            .map(|index| graph.insert(Health(index)))
+            .collect::<Vec<_>>();
+        for (index, node) in nodes.iter().enumerate() {
+            for step in [1, 7, 31] {
+                graph.relate::<Child>(*node, nodes[(index * step + step) % nodes.len()]);
+            }
+            if index % 3 == 0 {
+                graph.relate::<Effect>(*node, nodes[0]);
+            }
+        }
+        let expected_outgoing = nodes
+            .iter()
+            .map(|node| {
+                let mut result = graph.relations_outgoing::<Child>(*node).collect::<Vec<_>>();
+                result.sort();
+                result
+            })
+            .collect::<Vec<_>>();
+        let expected_incoming = {
+            let mut result = graph
+                .relations_incoming::<Effect>(nodes[0])
+                .collect::<Vec<_>>();
+            result.sort();
+            result
+        };
+        let expected_traverse = graph.relations_traverse::<Child>(nodes[5]).count();
+        graph.relate_with::<Health>(nodes[1], nodes[2], Health(7));
+
+        let frozen = graph.freeze();
+        for (node, expected) in nodes.iter().zip(expected_outgoing.iter()) {
+            let mut result = frozen
+                .relations_outgoing::<Child>(*node)
+                .collect::<Vec<_>>();
+            result.sort();
+            assert_eq!(&result, expected);
+            for target in expected {
+                assert!(frozen.are_related::<Child>(*node, *target));
+            }
+        }
+        let mut incoming = frozen
+            .relations_incoming::<Effect>(nodes[0])
+            .collect::<Vec<_>>();
+        incoming.sort();
+        assert_eq!(incoming, expected_incoming);
+        assert_eq!(
+            frozen.relations_traverse::<Child>(nodes[5]).count(),
+            expected_traverse
+        );
+        assert!(!frozen.are_related::<Parent>(nodes[0], nodes[1]));
+        assert_eq!(
+            frozen.graph().out_degree::<Child>(nodes[0]),
+            expected_outgoing[0].len()
+        );
+        assert_eq!(frozen.read::<Health>(nodes[3]).unwrap().0, 3);
+        assert_eq!(
+            frozen
+                .query::<Related<Child, &Health>>(nodes[1])
+                .map(|health| health.0)
+                .max(),
+            Some(62)
+        );
+
+        let mut graph = frozen.thaw();
+        for (node, expected) in nodes.iter().zip(expected_outgoing.iter()) {
+            let mut result = graph.relations_outgoing::<Child>(*node).collect::<Vec<_>>();
+            result.sort();
+            assert_eq!(&result, expected);
+        }
+        assert_eq!(
+            graph.relation_data::<Health>(nodes[1], nodes[2]).unwrap().0,
+            7
+        );
+        graph.unrelate::<Effect>(nodes[3], nodes[0]);
+        assert!(!graph.are_related::<Effect>(nodes[3], nodes[0]));
+    }
+
+    #[test]
+    fn test_relation_cardinality() {
+        let mut graph = Graph::default();
+        let child = graph.insert(());
+        let first = graph.insert(());
+        let second = graph.insert(());
+
+        graph.set_relation_cardinality::<Parent>(None, Some(1), CardinalityPolicy::EvictOldest);
+        graph.relate::<Parent>(first, child);
+        graph.relate::<Parent>(second, child);
+        assert_eq!(
+            graph
+                .relations_incoming::<Parent>(child)
+                .collect::<Vec<_>>(),
+            vec![second]
+        );
+        assert!(!graph.are_related::<Parent>(first, child));
+
+        graph.set_relation_cardinality::<Parent>(None, Some(1), CardinalityPolicy::Reject);
+        assert_eq!(
+            graph.try_relate::<Parent>(first, child),
+            Err(RelationError::CardinalityExceeded {
+                type_hash: TypeHash::of::<Parent>(),
+                from: first,
+                to: child,
+            })
+        );
+        assert_eq!(graph.try_relate::<Parent>(second, child), Ok(()));
+        graph.relate::<Parent>(first, child);
+        assert_eq!(
+            graph
+                .relations_incoming::<Parent>(child)
+                .collect::<Vec<_>>(),
+            vec![second]
+        );
+
+        graph.set_relation_cardinality::<Child>(Some(2), None, CardinalityPolicy::EvictOldest);
+        graph.relate::<Child>(first, child);
+        graph.relate::<Child>(first, second);
+        graph.relate::<Child>(first, first);
+        let mut children = graph.relations_outgoing::<Child>(first).collect::<Vec<_>>();
+        children.sort();
+        let mut expected = vec![second, first];
+        expected.sort();
+        assert_eq!(children, expected);
+
+        graph.set_relation_cardinality::<Parent>(None, None, CardinalityPolicy::Reject);
+        graph.relate::<Parent>(first, child);
+        assert_eq!(graph.relations_incoming::<Parent>(child).count(), 2);
+    }
+
+    #[test]
+    fn test_relate_pair_cardinality() {
+        let mut graph = Graph::default();
+        let child = graph.insert(());
+        let first = graph.insert(());
+        let second = graph.insert(());
+
+        graph.set_relation_cardinality::<Parent>(Some(1), None, CardinalityPolicy::EvictOldest);
+        graph.relate_pair::<Parent, Child>(first, child);
+        graph.relate_pair::<Parent, Child>(second, child);
+        assert!(graph.are_related::<Child>(second, child));
+        assert!(graph.are_related::<Parent>(child, second));
+        assert!(!graph.are_related::<Child>(first, child));
+        assert!(!graph.are_related::<Parent>(child, first));
+
+        graph.set_relation_cardinality::<Parent>(Some(1), None, CardinalityPolicy::Reject);
+        assert_eq!(
+            graph.try_relate_pair::<Parent, Child>(first, child),
+            Err(RelationError::CardinalityExceeded {
+                type_hash: TypeHash::of::<Parent>(),
+                from: child,
+                to: first,
+            })
+        );
+        assert!(!graph.are_related::<Child>(first, child));
+        assert!(!graph.are_related::<Parent>(child, first));
+        assert!(graph.are_related::<Child>(second, child));
+        assert!(graph.are_related::<Parent>(child, second));
+        assert_eq!(
+            graph.try_relate_pair::<Parent, Child>(second, child),
+            Ok(())
+        );
+
+        let mut graph = Graph::default();
+        let parent = graph.insert(());
+        let other = graph.insert(());
+        let first = graph.insert(());
+        let second = graph.insert(());
+        graph.set_relation_cardinality::<Child>(Some(1), None, CardinalityPolicy::EvictOldest);
+        graph.set_relation_cardinality::<Parent>(None, Some(2), CardinalityPolicy::EvictOldest);
+        graph.relate::<Parent>(other, parent);
+        graph.relate_pair::<Parent, Child>(parent, first);
+        graph.relate_pair::<Parent, Child>(parent, second);
+        assert!(graph.are_related::<Child>(parent, second));
+        assert!(graph.are_related::<Parent>(second, parent));
+        assert!(!graph.are_related::<Child>(parent, first));
+        assert!(!graph.are_related::<Parent>(first, parent));
+        assert!(graph.are_related::<Parent>(other, parent));
+    }
+
+    #[test]
+    fn test_merge_nodes() {
+        let mut graph = Graph::default();
+        let parent = graph.insert(());
+        let child = graph.insert(());
+        let effect = graph.insert(Fire);
+        let keep = graph.insert(Position(0, 0));
+        let remove = graph.insert(Health(1));
+        graph.relate::<Child>(parent, remove);
+        graph.relate::<Child>(remove, child);
+        graph.relate::<Effect>(remove, effect);
+        graph.relate::<Child>(keep, remove);
+        graph.relate::<Parent>(child, remove);
+
+        graph.merge_nodes(keep, remove).unwrap();
+        assert!(!graph.contains(remove));
+        assert!(graph.is::<Position>(keep));
+        assert!(graph.are_related::<Child>(parent, keep));
+        assert!(graph.are_related::<Child>(keep, child));
+        assert!(graph.are_related::<Effect>(keep, effect));
+        assert!(graph.are_related::<Parent>(child, keep));
+        assert!(!graph.are_related::<Child>(keep, keep));
+        assert!(
+            graph
+                .relations()
+                .all(|(_, from, to)| from != remove && to != remove)
+        );
+        assert!(graph.merge_nodes(keep, remove).is_err());
+
+        let other = graph.insert(Health(2));
+        graph.relate::<Child>(parent, other);
+        assert!(graph.merge_nodes(remove, other).is_err());
+        assert!(graph.contains(other));
+        assert!(graph.are_related::<Child>(parent, other));
+    }
+
+    #[test]
+    fn test_report() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let fire = graph.insert(Fire);
+        let player = graph.insert(Player);
+        graph.relate_pair::<Parent, Child>(root, player);
+        for index in 0..5 {
+            let tree = graph.insert(Tree);
+            graph.relate_pair::<Parent, Child>(root, tree);
+            let health = graph.insert(Health(2));
+            graph.relate_pair::<Parent, Child>(tree, health);
+            if index % 2 == 0 {
+                graph.relate::<Effect>(tree, fire);
+            }
+        }
+        let orphan = graph.insert(Tree);
+        graph.relate::<Effect>(orphan, orphan);
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Parent>().build())
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Effect>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Tree>().build())
+            .with_type(NativeStructBuilder::new::<Fire>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let report = graph.report(&registry);
+        assert_eq!(report.nodes, 14);
+        assert_eq!(report.nodes_per_type["Tree"], 6);
+        assert_eq!(report.nodes_per_type["Health"], 5);
+        assert_eq!(report.edges, 11 + 11 + 4);
+        assert_eq!(report.relations["Child"].edges, 11);
+        assert_eq!(report.relations["Child"].components, 1);
+        assert!(!report.relations["Child"].has_cycle);
+        assert_eq!(report.relations["Effect"].components, 2);
+        assert!(report.relations["Effect"].has_cycle);
+
+        let serialized = serde_json::to_value(&report).unwrap();
+        assert_eq!(serialized["nodes"], 14);
+        assert_eq!(serialized["relations"]["Parent"]["edges"], 11);
+    }
+
+    #[test]
+    fn test_try_traverse() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, c);
+
+        let order = graph.try_traverse::<Child>(root).unwrap();
+        assert_eq!(order.len(), 4);
+        assert_eq!(order[0], root);
+        assert!(order.contains(&c));
+
+        graph.relate::<Child>(c, a);
+        let cycle = graph.try_traverse::<Child>(root).unwrap_err();
+        assert_eq!(cycle.len(), 2);
+        assert!(cycle.contains(&a) && cycle.contains(&c));
+        assert!(graph.try_traverse::<Child>(b).is_err());
+        assert_eq!(graph.try_traverse::<Effect>(root), Ok(vec![root]));
+    }
+
+    #[test]
+    fn test_node_view() {
+        fn sorted(iter: impl Iterator<Item = AnyIndex>) -> Vec<AnyIndex> {
+            let mut result = iter.collect::<Vec<_>>();
+            result.sort();
+            result
+        }
+
+        let mut graph = Graph::default();
+        let body = graph.insert(());
+        let others = (0..6).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate::<Child>(body, others[0]);
+        graph.relate::<Child>(body, others[1]);
+        graph.relate::<Effect>(body, others[2]);
+        graph.relate::<Attribute>(body, others[3]);
+        graph.relate::<Parent>(others[4], body);
+        graph.relate::<Child>(others[5], body);
+
+        let view = graph.node_view(body);
+        assert_eq!(view.index(), body);
+        for _ in 0..3 {
+            assert_eq!(
+                sorted(view.outgoing::<Child>()),
+                sorted(graph.relations_outgoing::<Child>(body))
+            );
+            assert_eq!(
+                sorted(view.outgoing::<Effect>()),
+                sorted(graph.relations_outgoing::<Effect>(body))
+            );
+            assert_eq!(
+                sorted(view.outgoing::<Attribute>()),
+                sorted(graph.relations_outgoing::<Attribute>(body))
+            );
+        }
+        assert_eq!(
+            view.incoming::<Parent>().collect::<Vec<_>>(),
+            vec![others[4]]
+        );
+        assert_eq!(
+            view.incoming::<Child>().collect::<Vec<_>>(),
+            vec![others[5]]
+        );
+        assert!(view.outgoing::<Parent>().next().is_none());
+        assert_eq!(view.outgoing_categories().count(), 3);
+        assert_eq!(view.incoming_categories().count(), 2);
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut graph = Graph::default();
+        assert!(graph.is_empty());
+        assert_eq!(graph.len(), 0);
+        assert_eq!(graph.count_of::<Tree>(), 0);
+        assert_eq!(graph.relation_count::<Child>(), 0);
+
+        let root = graph.insert(());
+        let trees = (0..3).map(|_| graph.insert(Tree)).collect::<Vec<_>>();
+        assert!(!graph.is_empty());
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.count_of::<Tree>(), 3);
+        assert_eq!(graph.count_of::<()>(), 1);
+
+        for tree in &trees {
+            graph.relate::<Child>(root, *tree);
+        }
+        graph.relate::<Child>(root, trees[0]);
+        graph.relate::<Effect>(trees[0], trees[1]);
+        assert_eq!(graph.relation_count::<Child>(), 3);
+        assert_eq!(graph.relation_count::<Effect>(), 1);
+
+        graph.unrelate::<Child>(root, trees[2]);
+        assert_eq!(graph.relation_count::<Child>(), 2);
+
+        graph.remove(trees[0]).unwrap();
+        assert_eq!(graph.len(), 3);
+        assert_eq!(graph.count_of::<Tree>(), 2);
+        assert_eq!(graph.relation_count::<Effect>(), 0);
+    }
+
+    #[test]
+    fn test_degrees() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        assert_eq!(graph.out_degree::<Child>(a), 0);
+        assert_eq!(graph.in_degree::<Child>(a), 0);
+        assert_eq!(graph.out_degree_any(a), 0);
+
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Effect>(b, a);
+        graph.relate::<Effect>(a, a);
+        assert_eq!(graph.out_degree::<Child>(a), 2);
+        assert_eq!(graph.in_degree::<Child>(a), 0);
+        assert_eq!(graph.in_degree::<Child>(b), 1);
+        assert_eq!(graph.out_degree::<Parent>(a), 0);
+        assert_eq!(graph.in_degree_raw(b, TypeHash::of::<Parent>()), 0);
+        assert_eq!(graph.out_degree::<Effect>(a), 1);
+        assert_eq!(graph.in_degree::<Effect>(a), 2);
+        assert_eq!(graph.out_degree_raw(a, TypeHash::of::<Child>()), 2);
+        assert_eq!(graph.out_degree_any(a), 3);
+        assert_eq!(graph.in_degree_any(a), 2);
+        assert_eq!(graph.in_degree_any(c), 1);
+    }
+
+    #[test]
+    fn test_multi_edges() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Parent>(a, b);
+        graph.relate::<Attribute>(a, b);
+        graph.relate::<Parent>(b, c);
+        graph.relate::<Child>(c, b);
+
+        let result = graph.multi_edges().collect::<Vec<_>>();
+        let mut expected = vec![TypeHash::of::<Parent>(), TypeHash::of::<Attribute>()];
+        expected.sort();
+        assert_eq!(result, vec![(a, b, expected)]);
+
+        graph.unrelate::<Attribute>(a, b);
+        assert!(graph.multi_edges().next().is_none());
+    }
+
+    #[test]
+    fn test_k_shortest_paths() {
+        let mut graph = Graph::default();
+        let nodes = (0..7).map(|_| graph.insert(())).collect::<Vec<_>>();
+        let [from, a, b, c, d, e, to] = nodes[..] else {
+            unreachable!()
+        };
+        graph.relate::<Child>(from, a);
+        graph.relate::<Child>(a, to);
+        graph.relate::<Child>(from, b);
+        graph.relate::<Child>(b, to);
+        graph.relate::<Child>(from, c);
+        graph.relate::<Child>(c, d);
+        graph.relate::<Child>(d, e);
+        graph.relate::<Child>(e, to);
+        graph.relate::<Child>(to, from);
+
+        assert_eq!(graph.shortest_path::<Child>(from, to).unwrap().len(), 3);
+        let paths = graph.k_shortest_paths::<Child>(from, to, 3);
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0].len(), 3);
+        assert_eq!(paths[1].len(), 3);
+        assert_ne!(paths[0], paths[1]);
+        assert!(paths[..2].contains(&vec![from, a, to]));
+        assert!(paths[..2].contains(&vec![from, b, to]));
+        assert_eq!(paths[2], vec![from, c, d, e, to]);
+
+        assert_eq!(graph.k_shortest_paths::<Child>(from, to, 10).len(), 3);
+        assert!(graph.k_shortest_paths::<Child>(from, to, 0).is_empty());
+        assert!(graph.k_shortest_paths::<Effect>(from, to, 3).is_empty());
+        assert_eq!(graph.k_shortest_paths::<Child>(a, a, 2), vec![vec![a]]);
+    }
+
+    #[test]
+    fn test_shrink_relations() {
+        let mut graph = Graph::default();
+        let nodes = (0..20).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for round in 0..3 {
+            for (index, from) in nodes.iter().enumerate() {
+                graph.relate::<Child>(*from, nodes[(index + round + 1) % nodes.len()]);
+                graph.relate::<Effect>(*from, nodes[(index * 3) % nodes.len()]);
+                graph.relate::<Attribute>(*from, *from);
+            }
+            for (index, from) in nodes.iter().enumerate() {
+                graph.unrelate::<Child>(*from, nodes[(index + round + 1) % nodes.len()]);
+            }
+            graph.retain_relations::<Effect>(|_, _| false);
+            for from in &nodes {
+                graph.unrelate_all::<Attribute>(*from);
+            }
+            assert_eq!(graph.relations().count(), 0);
+            assert!(
+                graph
+                    .relations
+                    .values()
+                    .all(|relations| relations.is_empty())
+            );
+        }
+        assert_eq!(graph.relations.len(), 3);
+        graph.shrink_relations();
+        assert!(graph.relations.is_empty());
+
+        graph.unrelate::<Parent>(nodes[0], nodes[1]);
+        graph.unrelate_all::<Parent>(nodes[0]);
+        assert!(graph.relations.is_empty());
+    }
+
+    #[test]
+    fn test_map_relation() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Position(1, 2));
+        let a = graph.insert(Position(0, 0));
+        let b = graph.insert(Position(0, 0));
+        let c = graph.insert(Position(0, 0));
+        let health = graph.insert(Health(0));
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(root, health);
+
+        graph.map_relation::<Child, Position, Position>(|position| {
+            Position(position.0 + 1, position.1 + 1)
+        });
+        assert_eq!(graph.read::<Position>(root).unwrap().0, 1);
+        assert_eq!(graph.read::<Position>(a).unwrap().0, 2);
+        assert_eq!(graph.read::<Position>(b).unwrap().1, 3);
+        assert_eq!(graph.read::<Position>(c).unwrap().0, 1);
+        assert_eq!(graph.read::<Health>(health).unwrap().0, 0);
+
+        graph.map_relation::<Child, Position, Health>(|position| Health(position.0 as usize));
+        assert_eq!(graph.read::<Health>(health).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_reverse_topological_sort() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+        graph.relate::<()>(a, b);
+        graph.relate::<()>(a, c);
+        graph.relate::<()>(b, d);
+        graph.relate::<()>(c, d);
+
+        let order = graph.reverse_topological_sort::<()>().unwrap();
+        let position = |index| order.iter().position(|item| *item == index).unwrap();
+        assert_eq!(order.len(), 4);
+        assert_eq!(order[0], d);
+        assert_eq!(order[3], a);
+        assert!(position(b) < position(a) && position(c) < position(a));
+
+        graph.relate::<()>(d, a);
+        let cycle = graph.reverse_topological_sort::<()>().unwrap_err();
+        assert!(!cycle.is_empty());
+        assert!(cycle.contains(&a) && cycle.contains(&d));
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+        let e = graph.insert(());
+        let f = graph.insert(());
+        let lonely = graph.insert(());
+        graph.relate::<()>(a, b);
+        graph.relate::<()>(a, c);
+        graph.relate::<()>(b, d);
+        graph.relate::<()>(c, d);
+        graph.relate::<()>(f, e);
+
+        let order = graph.topological_sort::<()>().unwrap();
+        assert_eq!(order.len(), 6);
+        assert!(!order.contains(&lonely));
+        let position = |index| order.iter().position(|item| *item == index).unwrap();
+        for (from, to) in graph.relations_outgoing_all::<()>() {
+            assert!(position(from) < position(to));
+        }
+        assert!(graph.topological_sort::<Child>().unwrap().is_empty());
+
+        graph.relate::<()>(d, a);
+        let cycle = graph.topological_sort::<()>().unwrap_err();
+        assert!(cycle.len() >= 3);
+        for (from, to) in cycle.iter().zip(cycle.iter().cycle().skip(1)) {
+            assert!(graph.are_related::<()>(*from, *to));
+        }
+    }
+
+    #[test]
+    fn test_relation_capacity() {
+        let mut graph = Graph::default()
+            .with_relation_capacity::<Child>(1000)
+            .with_relation_capacity::<Effect>(4);
+        let child_capacity = graph.relation_capacity::<Child>();
+        assert!(child_capacity >= 1000);
+        assert!(graph.relation_capacity::<Effect>() >= 4);
+
+        let nodes = (0..1001).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for pair in nodes.windows(2) {
+            graph.relate::<Child>(pair[0], pair[1]);
+        }
+        assert_eq!(graph.relation_count::<Child>(), 1000);
+        assert_eq!(graph.relation_capacity::<Child>(), child_capacity);
+        assert_eq!(graph.relation_capacity::<Parent>(), 0);
+    }
+
+    #[test]
+    fn test_capacity_builders_compose() {
+        let mut graph = Graph::default()
+            .with_relation_capacity::<Child>(100)
+            .with_new_arena_capacity(64);
+        assert!(graph.relation_capacity::<Child>() >= 100);
+        graph.insert(Tree);
+        assert_eq!(graph.node_capacity::<Tree>(), 64);
+
+        let mut graph = Graph::default()
+            .with_new_arena_capacity(64)
+            .with_relation_capacity::<Child>(100);
+        assert!(graph.relation_capacity::<Child>() >= 100);
+        graph.insert(Tree);
+        assert_eq!(graph.node_capacity::<Tree>(), 64);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+        let e = graph.insert(());
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, d);
+        graph.relate::<Child>(c, d);
+        graph.relate::<Child>(d, e);
+
+        assert_eq!(graph.shortest_path::<Child>(a, a), Some(vec![a]));
+        let path = graph.shortest_path::<Child>(a, e).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[0], a);
+        assert_eq!(path[3], e);
+        for pair in path.windows(2) {
+            assert!(graph.are_related::<Child>(pair[0], pair[1]));
+        }
+        assert_eq!(graph.shortest_path::<Child>(b, e), Some(vec![b, d, e]));
+        assert_eq!(graph.shortest_path::<Child>(e, a), None);
+        assert_eq!(graph.shortest_path::<Effect>(a, b), None);
+    }
+
+    #[test]
+    fn test_nth_ancestor() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let middle = graph.insert(());
+        let leaf = graph.insert(());
+        graph.relate::<Child>(root, middle);
+        graph.relate::<Child>(middle, leaf);
+
+        assert_eq!(graph.nth_ancestor::<Child>(leaf, 0), Some(leaf));
+        assert_eq!(graph.nth_ancestor::<Child>(leaf, 1), Some(middle));
+        assert_eq!(graph.nth_ancestor::<Child>(leaf, 2), Some(root));
+        assert_eq!(graph.nth_ancestor::<Child>(leaf, 3), None);
+        assert_eq!(graph.nth_ancestor::<Effect>(leaf, 1), None);
+
+        graph.relate::<Child>(leaf, root);
+        assert_eq!(graph.nth_ancestor::<Child>(leaf, 2), Some(root));
+        assert_eq!(graph.nth_ancestor::<Child>(leaf, 3), None);
+
+        let first = graph.insert(());
+        let second = graph.insert(());
+        let child = graph.insert(());
+        let grandparent = graph.insert(());
+        graph.relate::<Effect>(second, child);
+        graph.relate::<Effect>(first, child);
+        graph.relate::<Effect>(grandparent, first);
+        assert!(first < second);
+        for _ in 0..10 {
+            assert_eq!(graph.nth_ancestor::<Effect>(child, 1), Some(first));
+            assert_eq!(graph.nth_ancestor::<Effect>(child, 2), Some(grandparent));
+        }
+        graph.unrelate::<Effect>(first, child);
+        assert_eq!(graph.nth_ancestor::<Effect>(child, 1), Some(second));
+        assert_eq!(graph.nth_ancestor::<Effect>(child, 2), None);
+    }
+
+    #[test]
+    fn test_references() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        assert!(!graph.is_referenced(a));
+        assert!(!graph.has_references(a));
+
+        graph.relate::<Effect>(a, b);
+        assert!(graph.has_references(a));
+        assert!(!graph.is_referenced(a));
+        assert!(graph.is_referenced(b));
+        assert!(!graph.has_references(b));
+
+        graph.unrelate::<Effect>(a, b);
+        assert!(!graph.has_references(a));
+        assert!(!graph.is_referenced(b));
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let mut graph = Graph::default();
+        let nodes = (0..7).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate_pair::<Child, Child>(nodes[0], nodes[1]);
+        graph.relate::<Child>(nodes[2], nodes[1]);
+        graph.relate::<Child>(nodes[3], nodes[4]);
+        graph.relate::<Child>(nodes[5], nodes[4]);
+        graph.relate::<Effect>(nodes[2], nodes[3]);
+
+        let mut components = graph.connected_components::<Child>();
+        components.sort();
+        assert_eq!(
+            components,
+            vec![
+                vec![nodes[0], nodes[1], nodes[2]],
+                vec![nodes[3], nodes[4], nodes[5]],
+            ]
+        );
+        assert!(
+            components
+                .iter()
+                .all(|component| !component.contains(&nodes[6]))
+        );
+        assert!(graph.connected_components::<Parent>().is_empty());
+    }
+
+    #[test]
+    fn test_reachable_sets() {
+        let mut graph = Graph::default();
+        let nodes = (0..6).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate::<Child>(nodes[0], nodes[1]);
+        graph.relate::<Child>(nodes[1], nodes[2]);
+        graph.relate::<Child>(nodes[3], nodes[2]);
+        graph.relate::<Child>(nodes[2], nodes[4]);
+        graph.relate::<Effect>(nodes[3], nodes[5]);
+
+        let sets = graph.reachable_sets::<Child>(&[nodes[0], nodes[3]]);
+        assert_eq!(sets.len(), 2);
+        assert_eq!(
+            sets[&nodes[0]],
+            HashSet::from([nodes[0], nodes[1], nodes[2], nodes[4]])
+        );
+        assert_eq!(
+            sets[&nodes[3]],
+            HashSet::from([nodes[3], nodes[2], nodes[4]])
+        );
+    }
+
+    #[test]
+    fn test_prefab_read_nodes() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Player);
+        let a = graph.insert(Health(10));
+        let b = graph.insert(Health(20));
+        let position = graph.insert(Position(1, 2));
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(root, position);
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Fire>().build())
+            .with_type(NativeStructBuilder::new::<Position>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Child>()
+            .with_serde::<Player>()
+            .with_serde::<Position>()
+            .with_serde::<Health>();
+
+        let prefab = Prefab::from_graph(&graph, &serialization, &registry).unwrap();
+        let mut healths = prefab
+            .read_nodes::<Health>(&serialization, &registry)
+            .unwrap()
+            .into_iter()
+            .map(|(index, health)| (index, health.0))
+            .collect::<Vec<_>>();
+        healths.sort();
+        assert_eq!(healths, vec![(a.index(), 10), (b.index(), 20)]);
+        assert!(
+            prefab
+                .read_nodes::<Tree>(&serialization, &registry)
+                .is_err()
+        );
+        assert!(
+            prefab
+                .read_nodes::<Fire>(&serialization, &registry)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_validate_pair() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate_pair::<Parent, Child>(root, a);
+        assert!(graph.validate_pair::<Parent, Child>().is_empty());
+
+        graph.relate::<Child>(root, b);
+        graph.relate::<Parent>(c, root);
+        assert_eq!(graph.validate_pair::<Parent, Child>(), {
+            let mut expected = vec![(root, b), (root, c)];
+            expected.sort();
+            expected
+        });
+
+        graph.relate::<Parent>(b, root);
+        graph.relate::<Child>(root, c);
+        assert!(graph.validate_pair::<Parent, Child>().is_empty());
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let mut graph = Graph::default();
+        let world = graph.insert(Player);
+        let root = graph.insert(Tree);
+        let a = graph.insert(Health(10));
+        let b = graph.insert(Position(1, 2));
+        let outside = graph.insert(Health(99));
+        graph.relate::<Child>(world, root);
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(world, outside);
+        graph.relate::<Effect>(b, outside);
+        graph.relate::<Effect>(a, b);
+        graph.add_tag::<Fire>(a);
+        graph.add_tag::<Fire>(outside);
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Tree>().build())
+            .with_type(NativeStructBuilder::new::<Position>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Player>()
+            .with_serde::<Tree>()
+            .with_serde::<Position>()
+            .with_serde::<Health>();
+
+        let (subgraph, mappings) = graph
+            .subgraph::<Child>(root, &serialization, &registry)
+            .unwrap();
+        assert_eq!(subgraph.len(), 3);
+        assert_eq!(mappings.len(), 3);
+        assert!(!mappings.contains_key(&world));
+        assert!(!mappings.contains_key(&outside));
+        let (root2, a2, b2) = (mappings[&root], mappings[&a], mappings[&b]);
+        assert!(subgraph.is::<Tree>(root2));
+        assert_eq!(subgraph.read::<Health>(a2).unwrap().0, 10);
+        assert_eq!(subgraph.read::<Position>(b2).unwrap().1, 2);
+        assert!(subgraph.are_related::<Child>(root2, a2));
+        assert!(subgraph.are_related::<Child>(a2, b2));
+        assert!(subgraph.are_related::<Effect>(a2, b2));
+        assert_eq!(subgraph.relation_count::<Child>(), 2);
+        assert_eq!(subgraph.relation_count::<Effect>(), 1);
+        assert!(subgraph.has_tag::<Fire>(a2));
+        assert_eq!(subgraph.tagged::<Fire>().count(), 1);
+    }
+
+    #[test]
+    fn test_relation_categories() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Effect>(b, c);
+
+        let mut categories = graph.relation_categories().collect::<Vec<_>>();
+        categories.sort();
+        let mut expected = vec![TypeHash::of::<Child>(), TypeHash::of::<Effect>()];
+        expected.sort();
+        assert_eq!(categories, expected);
+        assert!(graph.has_relation_category::<Child>());
+        assert!(graph.has_relation_category::<Effect>());
+        assert!(!graph.has_relation_category::<Parent>());
+
+        graph.unrelate::<Effect>(b, c);
+        assert!(!graph.has_relation_category::<Effect>());
+        assert_eq!(
+            graph.relation_categories().collect::<Vec<_>>(),
+            vec![TypeHash::of::<Child>()]
+        );
+    }
+
+    #[test]
+    fn test_collect_type() {
+        let mut graph = Graph::default();
+        let a = graph.insert(Position(1, 2));
+        let b = graph.insert(Position(3, 4));
+        graph.insert(Health(5));
+
+        let mut positions = graph.collect_type::<Position>();
+        positions.sort_by_key(|(index, _)| *index);
+        drop(graph);
+        let mut expected = vec![(a, Position(1, 2)), (b, Position(3, 4))];
+        expected.sort_by_key(|(index, _)| *index);
+        assert_eq!(positions, expected);
+    }
+
+    #[test]
+    fn test_for_each_edge() {
+        let mut graph = Graph::default();
+        let nodes = (0..5).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate::<Child>(nodes[0], nodes[1]);
+        graph.relate::<Child>(nodes[0], nodes[2]);
+        graph.relate::<Child>(nodes[2], nodes[3]);
+        graph.relate::<Effect>(nodes[3], nodes[4]);
+
+        let mut edges = Vec::default();
+        graph.for_each_edge::<Child>(|from, to| edges.push((from, to)));
+        let mut expected = graph.relations_outgoing_all::<Child>().collect::<Vec<_>>();
+        edges.sort();
+        expected.sort();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(edges, expected);
+
+        let mut count = 0;
+        graph.for_each_edge::<Parent>(|_, _| count += 1);
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_clear_relations() {
+        let mut graph = Graph::default();
+        let nodes = (0..10).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for pair in nodes.windows(2) {
+            graph.relate::<Child>(pair[0], pair[1]);
+            graph.relate::<Parent>(pair[1], pair[0]);
+        }
+        graph.relate::<Effect>(nodes[0], nodes[9]);
+
+        graph.clear_relations::<Child>();
+        assert!(
+            graph
+                .relations()
+                .all(|(type_hash, _, _)| type_hash != TypeHash::of::<Child>())
+        );
+        assert!(
+            nodes
+                .iter()
+                .all(|index| graph.relations_incoming::<Child>(*index).next().is_none())
+        );
+        assert_eq!(graph.relation_count::<Parent>(), 9);
+
+        graph.clear_relations_raw(TypeHash::of::<Parent>());
+        assert_eq!(graph.relations().count(), 1);
+        assert!(graph.are_related::<Effect>(nodes[0], nodes[9]));
+    }
+
+    #[test]
+    fn test_remap_relation() {
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate::<Parent>(a, b);
+        graph.relate::<Parent>(b, c);
+        graph.relate::<Child>(c, a);
+        graph.relate::<Child>(a, b);
+
+        graph.remap_relation::<Parent, Child>();
+        assert!(!graph.has_relation_category::<Parent>());
+        assert!(!graph.are_related::<Parent>(a, b));
+        assert!(graph.are_related::<Child>(a, b));
+        assert!(graph.are_related::<Child>(b, c));
+        assert!(graph.are_related::<Child>(c, a));
+        assert_eq!(graph.relation_count::<Child>(), 3);
+        assert!(graph.relations_incoming::<Child>(c).eq([b]));
+
+        graph.remap_relation::<Child, Effect>();
+        assert!(!graph.has_relation_category::<Child>());
+        assert_eq!(graph.relation_count::<Effect>(), 3);
+    }
+
+    #[test]
+    fn test_empty_node() {
+        let mut graph = Graph::default();
+        let root = graph.insert_empty();
+        let health = graph.insert(Health(1));
+        graph.relate::<Child>(root, health);
+
+        assert!(graph.is_empty_node(root));
+        assert!(graph.is::<()>(root));
+        assert!(!graph.is_empty_node(health));
+
+        graph.remove(root).unwrap();
+        assert!(!graph.is_empty_node(root));
+    }
+
+    #[test]
+    fn test_query_count() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(Tree);
+        let b = graph.insert(Tree);
+        let c = graph.insert(Tree);
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(root, c);
+        for _ in 0..3 {
+            let health = graph.insert(Health(1));
+            graph.relate::<Child>(a, health);
+        }
+        let health = graph.insert(Health(1));
+        graph.relate::<Child>(b, health);
+
+        let mut counts = graph
+            .query::<Related<Child, Query<Node<Tree>, (AnyIndex, Count<Child>)>>>(root)
+            .collect::<Vec<_>>();
+        counts.sort();
+        let mut expected = vec![(a, 3), (b, 1), (c, 0)];
+        expected.sort();
+        assert_eq!(counts, expected);
+
+        assert!(graph.query::<Count<Child>>(root).eq([3]));
+        assert!(graph.query::<(Count<Child>, Count<Parent>)>(a).eq([(3, 0)]));
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Player);
+        let tree = graph.insert(Tree);
+        graph.relate::<Child>(root, tree);
+        graph.relate::<Effect>(root, tree);
+        graph.relate::<Effect>(tree, tree);
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Effect>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Tree>().build());
+
+        let dot = graph.to_dot(&registry);
+        let (root, tree) = if root < tree {
+            ("n0", "n1")
+        } else {
+            ("n1", "n0")
+        };
+        assert!(dot.starts_with("digraph G {"));
+        assert!(dot.contains(&format!(r#"{root} [label="Player"];"#)));
+        assert!(dot.contains(&format!(r#"{tree} [label="Tree"];"#)));
+        assert!(dot.contains(&format!(
+            r#"{root} -> {tree} [label="Child", color="black""#
+        )));
+        assert!(dot.contains(&format!(r#"{root} -> {tree} [label="Effect", color="red""#)));
+        assert!(dot.contains(&format!(r#"{tree} -> {tree} [label="Effect", color="red""#)));
+        assert_eq!(dot.matches(" -> ").count(), 3);
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_petgraph() {
+        let mut graph = Graph::default();
+        let nodes = (0..5).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate::<Child>(nodes[0], nodes[1]);
+        graph.relate::<Child>(nodes[0], nodes[2]);
+        graph.relate::<Child>(nodes[2], nodes[3]);
+        graph.relate::<Child>(nodes[3], nodes[3]);
+        graph.relate::<Effect>(nodes[1], nodes[4]);
+
+        let (petgraph, mappings) = graph.to_petgraph::<Child>();
+        assert_eq!(petgraph.node_count(), 5);
+        assert_eq!(petgraph.edge_count(), 4);
+        assert_eq!(mappings.len(), 5);
+        for (index, node) in &mappings {
+            assert_eq!(petgraph[*node], *index);
+        }
+        assert!(petgraph.contains_edge(mappings[&nodes[2]], mappings[&nodes[3]]));
+
+        assert_eq!(graph.relate_from_petgraph::<Parent, _>(&petgraph), 4);
+        let mut expected = graph.relations_outgoing_all::<Child>().collect::<Vec<_>>();
+        let mut imported = graph.relations_outgoing_all::<Parent>().collect::<Vec<_>>();
+        expected.sort();
+        imported.sort();
+        assert_eq!(imported, expected);
+    }
+
+    #[test]
+    fn test_nodes_storage() {
+        let mut graph = Graph::default();
+        graph.insert(Player);
+        graph.insert(Tree);
+        graph.insert(Tree);
+        let health = graph.insert(Health(1));
+        graph.remove(health).unwrap();
+
+        let arenas = graph
+            .nodes()
+            .arenas()
+            .iter()
+            .filter(|arena| !arena.is_empty())
+            .count();
+        assert_eq!(arenas, 2);
+        assert_eq!(arenas, graph.node_type_hashes().count());
+        assert!(
+            graph
+                .node_type_hashes()
+                .any(|type_hash| type_hash == TypeHash::of::<Tree>())
+        );
+        assert_eq!(graph.nodes().len(), graph.len());
+
+        let index = unsafe { graph.nodes_mut() }.insert(Fire);
+        assert!(graph.is::<Fire>(index));
+        assert_eq!(graph.node_type_hashes().count(), 3);
+    }
+
+    #[test]
+    fn test_graph_snapshot() {
+        use serde::de::DeserializeSeed;
+
+        let mut graph = Graph::default();
+        let root = graph.insert(Player);
+        let health = graph.insert(Health(7));
+        let position = graph.insert(Position(1, 2));
+        graph.relate::<Child>(root, health);
+        graph.relate::<Child>(root, position);
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Position>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Child>()
+            .with_serde::<Player>()
+            .with_serde::<Position>()
+            .with_serde::<Health>();
+
+        let json = serde_json::to_string(&graph.snapshot(&serialization, &registry)).unwrap();
+        let graph2 = Graph::snapshot_seed(&serialization, &registry)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+        assert_eq!(graph2.len(), 3);
+        assert_eq!(graph2.relation_count::<Child>(), 2);
+        assert_eq!(graph2.iter::<Health>().next().unwrap().1.0, 7);
+        assert_eq!(graph2.collect_type::<Position>()[0].1, Position(1, 2));
+
+        let prefab = graph.serialize_with(&serialization, &registry).unwrap();
+        let graph3 = Graph::deserialize_from(&prefab, &serialization, &registry).unwrap();
+        assert_eq!(graph3.len(), 3);
+        assert_eq!(graph3.relation_count::<Child>(), 2);
+    }
+
+    #[test]
+    fn test_relation_payloads() {
+        struct Road(f32);
+
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate_with(a, b, Road(1.5));
+        graph.relate_with(b, c, Road(2.0));
+        graph.relate::<Road>(a, c);
+
+        assert!(graph.are_related::<Road>(a, b));
+        assert_eq!(graph.relation_data::<Road>(a, b).unwrap().0, 1.5);
+        assert_eq!(graph.relation_data::<Road>(b, c).unwrap().0, 2.0);
+        assert!(graph.relation_data::<Road>(a, c).is_none());
+        assert!(graph.relation_data::<Road>(b, a).is_none());
+
+        graph.relation_data_mut::<Road>(a, b).unwrap().0 += 1.0;
+        assert_eq!(graph.relation_data::<Road>(a, b).unwrap().0, 2.5);
+
+        graph.relate_with(a, b, Road(10.0));
+        assert_eq!(graph.relation_data::<Road>(a, b).unwrap().0, 10.0);
+        assert_eq!(graph.relation_count::<Road>(), 3);
+
+        graph.unrelate::<Road>(a, b);
+        assert!(graph.relation_data::<Road>(a, b).is_none());
+        graph.relate::<Road>(a, b);
+        assert!(graph.relation_data::<Road>(a, b).is_none());
+
+        graph.remove(b).unwrap();
+        assert!(graph.relation_data::<Road>(b, c).is_none());
+    }
+
+    #[test]
+    fn test_relation_payloads_preserved() {
+        #[derive(Default, Serialize, Deserialize)]
+        struct Road(f32);
+        #[derive(Default)]
+        struct Rail;
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Road>().build())
+            .with_type(NativeStructBuilder::new::<Rail>().build())
+            .with_type(NativeStructBuilder::new::<Effect>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Road>();
+
+        let mut graph = Graph::default();
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        graph.relate_with(a, b, Road(1.5));
+        graph.relate::<Road>(b, c);
+
+        let prefab = Prefab::from_graph(&graph, &serialization, &registry).unwrap();
+        let json = serde_json::to_string(&prefab).unwrap();
+        let prefab = serde_json::from_str::<Prefab>(&json).unwrap();
+        let (restored, mappings) = prefab.to_graph(&serialization, &registry).unwrap();
+        assert_eq!(
+            restored
+                .relation_data::<Road>(mappings[&a], mappings[&b])
+                .unwrap()
+                .0,
+            1.5
+        );
+        assert!(restored.are_related::<Road>(mappings[&b], mappings[&c]));
+        assert!(
+            restored
+                .relation_data::<Road>(mappings[&b], mappings[&c])
+                .is_none()
+        );
+
+        let (subgraph, mappings) = graph
+            .subgraph::<Road>(a, &serialization, &registry)
+            .unwrap();
+        assert_eq!(
+            subgraph
+                .relation_data::<Road>(mappings[&a], mappings[&b])
+                .unwrap()
+                .0,
+            1.5
+        );
+
+        let d = graph.insert(());
+        graph.relate_with(d, a, Road(4.0));
+        graph.relate_with(d, c, Road(5.0));
+        graph.merge_nodes(b, d).unwrap();
+        assert_eq!(graph.relation_data::<Road>(b, a).unwrap().0, 4.0);
+        assert!(graph.relation_data::<Road>(b, c).is_none());
+
+        graph.remap_relation::<Road, Effect>();
+        assert!(graph.are_related::<Effect>(a, b));
+        assert!(graph.relation_data::<Effect>(a, b).is_none());
+
+        graph.relate_with(a, c, Rail);
+        assert!(matches!(
+            Prefab::from_graph(&graph, &serialization, &registry),
+            Err(PrefabError::CouldNotSerializeType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_traverse_depth() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(c, d);
+        graph.relate::<Child>(d, root);
+
+        let mut result = graph
+            .query::<TraverseDepth<1, Child, AnyIndex>>(root)
+            .collect::<Vec<_>>();
+        result.sort();
+        let mut expected = vec![root, a, b];
+        expected.sort();
+        assert_eq!(result, expected);
+
+        assert!(
+            graph
+                .query::<TraverseDepth<0, Child, AnyIndex>>(root)
+                .eq([root])
+        );
+        assert_eq!(
+            graph
+                .query::<TraverseDepth<2, Child, AnyIndex>>(root)
+                .count(),
+            4
+        );
+        assert_eq!(
+            graph
+                .query::<TraverseDepth<10, Child, AnyIndex>>(root)
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_edge_betweenness() {
+        let mut graph = Graph::default();
+        let nodes = (0..6).map(|_| graph.insert(())).collect::<Vec<_>>();
+        graph.relate::<Child>(nodes[0], nodes[1]);
+        graph.relate::<Child>(nodes[1], nodes[2]);
+        graph.relate::<Child>(nodes[2], nodes[0]);
+        graph.relate::<Child>(nodes[3], nodes[4]);
+        graph.relate::<Child>(nodes[4], nodes[5]);
+        graph.relate::<Child>(nodes[5], nodes[3]);
+        graph.relate::<Child>(nodes[2], nodes[3]);
+
+        let betweenness = graph.edge_betweenness::<Child>();
+        assert_eq!(betweenness.len(), 7);
+        let bridge = betweenness[&(nodes[2], nodes[3])];
+        assert!((bridge - 9.0).abs() < 1.0e-9);
+        assert!(
+            betweenness
+                .iter()
+                .filter(|(edge, _)| **edge != (nodes[2], nodes[3]))
+                .all(|(_, value)| *value < bridge)
+        );
+        assert!((betweenness[&(nodes[0], nodes[1])] - 1.0).abs() < 1.0e-9);
+        assert!((betweenness[&(nodes[1], nodes[2])] - 4.0).abs() < 1.0e-9);
+        assert!(graph.edge_betweenness::<Parent>().is_empty());
+    }
+
+    #[test]
+    fn test_query_or() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let controller = graph.insert(Controller { forward: true });
+        let position = graph.insert(Position(0, 0));
+        let health = graph.insert(Health(1));
+        graph.relate::<Child>(root, controller);
+        graph.relate::<Child>(root, position);
+        graph.relate::<Child>(root, health);
+
+        let mut result = graph
+            .query::<Related<Child, Or<Node<Controller>, Node<Position>>>>(root)
+            .collect::<Vec<_>>();
+        result.sort();
+        let mut expected = vec![controller, position];
+        expected.sort();
+        assert_eq!(result, expected);
+
+        let result = graph
+            .query::<Related<Child, Query<Or<Node<Health>, Node<Position>>, &Health>>>(root)
+            .map(|health| health.0)
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![1]);
+
+        assert!(
+            graph
+                .query::<Related<Child, Or<Node<Tree>, Node<Fire>>>>(root)
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_query_not() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let fire = graph.insert(Fire);
+        let burning = graph.insert(Tree);
+        let healthy = graph.insert(Tree);
+        let affected = graph.insert(Tree);
+        let effect = graph.insert(Effect);
+        graph.relate::<Child>(root, burning);
+        graph.relate::<Child>(root, healthy);
+        graph.relate::<Child>(root, affected);
+        graph.relate::<Effect>(burning, fire);
+        graph.relate::<Effect>(affected, effect);
+
+        let mut result = graph
+            .query::<Related<Child, Query<Node<Tree>, (AnyIndex, Not<Related<Effect, Is<Fire>>>)>>>(
+                root,
+            )
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        result.sort();
+        let mut expected = vec![healthy, affected];
+        expected.sort();
+        assert_eq!(result, expected);
+
+        assert!(
+            graph
+                .query::<(AnyIndex, Not<Related<Effect, AnyIndex>>)>(healthy)
+                .map(|(index, _)| index)
+                .eq([healthy])
+        );
+        assert!(
+            graph
+                .query::<Not<Related<Effect, AnyIndex>>>(burning)
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_insert_child() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert_child::<Parent, Child, _>(root, Health(1));
+        let b = graph.insert_child::<Parent, Child, _>(root, Health(2));
+        let c = graph.insert_child::<Parent, Child, _>(a, Tree);
+
+        let mut manual = Graph::default();
+        let manual_root = manual.insert(());
+        let manual_a = manual.insert(Health(1));
+        manual.relate_pair::<Parent, Child>(manual_root, manual_a);
+        let manual_b = manual.insert(Health(2));
+        manual.relate_pair::<Parent, Child>(manual_root, manual_b);
+        let manual_c = manual.insert(Tree);
+        manual.relate_pair::<Parent, Child>(manual_a, manual_c);
+
+        assert_eq!((root, a, b, c), (manual_root, manual_a, manual_b, manual_c));
+        assert_eq!(graph.relations, manual.relations);
+        let mut healths = graph
+            .query::<Related<Child, &Health>>(root)
+            .map(|health| health.0)
+            .collect::<Vec<_>>();
+        healths.sort();
+        assert_eq!(healths, vec![1, 2]);
+        assert!(graph.are_related::<Parent>(c, a));
+    }
+
+    #[test]
+    fn test_command_log() {
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Effect>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Tree>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Child>()
+            .with_serde::<Effect>()
+            .with_serde::<Player>()
+            .with_serde::<Tree>()
+            .with_serde::<Health>();
+
+        let mut graph = Graph::default();
+        graph.start_recording();
+        assert!(graph.is_recording());
+        let insert = |graph: &mut Graph, command: GraphCommand| {
+            graph
+                .apply(command, &serialization, &registry)
+                .unwrap()
+                .unwrap()
+        };
+        let player = insert(
+            &mut graph,
+            GraphCommand::insert(&Player, &serialization, &registry).unwrap(),
+        );
+        let trees = (0..3)
+            .map(|_| {
+                insert(
+                    &mut graph,
+                    GraphCommand::insert(&Tree, &serialization, &registry).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let health = insert(
+            &mut graph,
+            GraphCommand::insert(&Health(42), &serialization, &registry).unwrap(),
+        );
+        for tree in &trees {
+            graph
+                .apply(
+                    GraphCommand::relate::<Child>(player, *tree, &registry).unwrap(),
+                    &serialization,
+                    &registry,
+                )
+                .unwrap();
+        }
+        for command in [
+            GraphCommand::relate::<Effect>(trees[0], health, &registry).unwrap(),
+            GraphCommand::unrelate::<Child>(player, trees[1], &registry).unwrap(),
+            GraphCommand::remove(trees[2], &registry).unwrap(),
+        ] {
+            graph.apply(command, &serialization, &registry).unwrap();
+        }
+        let log = graph.stop_recording();
+        assert!(!graph.is_recording());
+        assert_eq!(log.len(), 11);
+
+        let json = serde_json::to_string(&log).unwrap();
+        let log = serde_json::from_str::<Vec<GraphCommand>>(&json).unwrap();
+        let mut replayed = Graph::default();
+        for command in log {
+            replayed.apply(command, &serialization, &registry).unwrap();
+        }
+        let mut indices = graph.indices().collect::<Vec<_>>();
+        let mut replayed_indices = replayed.indices().collect::<Vec<_>>();
+        indices.sort();
+        replayed_indices.sort();
+        assert_eq!(indices, replayed_indices);
+        assert_eq!(graph.relations, replayed.relations);
+        assert_eq!(replayed.read::<Health>(health).unwrap().0, 42);
+        assert!(replayed.are_related::<Child>(player, trees[0]));
+        assert!(!replayed.are_related::<Child>(player, trees[1]));
+        assert!(!replayed.contains(trees[2]));
+    }
+
+    #[test]
+    fn test_related_incoming() {
+        let mut graph = Graph::default();
+        let a = graph.insert(Player);
+        let b = graph.insert(Tree);
+        let c = graph.insert(Health(3));
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(b, c);
+
+        assert!(graph.query::<RelatedIncoming<Child, AnyIndex>>(b).eq([a]));
+        assert!(
+            graph
+                .query::<RelatedIncoming<Child, Is<Player>>>(b)
+                .eq([()])
+        );
+        assert!(
+            graph
+                .query::<RelatedIncoming<Child, Is<Tree>>>(b)
+                .next()
+                .is_none()
+        );
+        assert!(
+            graph
+                .query::<RelatedIncoming<Child, AnyIndex>>(a)
+                .next()
+                .is_none()
+        );
+        assert!(
+            graph
+                .query::<RelatedIncoming<Parent, AnyIndex>>(b)
+                .next()
+                .is_none()
+        );
+        assert!(
+            graph
+                .query::<RelatedIncoming<Child, Query<Node<Tree>, RelatedIncoming<Child, AnyIndex>>>>(c)
+                .eq([a])
+        );
+        assert_eq!(
+            graph
+                .query::<Related<
+                    Child,
+                    Query<AnyIndex, (RelatedIncoming<Child, AnyIndex>, Related<Child, &Health>)>,
+                >>(a)
+                .map(|(parent, health)| (parent, health.0))
+                .collect::<Vec<_>>(),
+            vec![(a, 3)]
+        );
+    }
+
+    #[test]
+    fn test_nodes_at_depth() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let mut grandchildren = Vec::default();
+        for _ in 0..2 {
+            let child = graph.insert(());
+            graph.relate::<Child>(root, child);
+            for _ in 0..2 {
+                let grandchild = graph.insert(());
+                graph.relate::<Child>(child, grandchild);
+                grandchildren.push(grandchild);
+            }
+        }
+        graph.relate::<Child>(grandchildren[0], root);
+
+        let mut result = graph.nodes_at_depth::<Child>(root, 2);
+        result.sort();
+        grandchildren.sort();
+        assert_eq!(result, grandchildren);
+        assert_eq!(graph.nodes_at_depth::<Child>(root, 0), vec![root]);
+        assert_eq!(graph.nodes_at_depth::<Child>(root, 1).len(), 2);
+        assert!(graph.nodes_at_depth::<Child>(root, 3).is_empty());
+    }
+
+    #[test]
+    fn test_relations_traverse_depth() {
+        let mut graph = Graph::default();
+        let chain = (0..5).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for pair in chain.windows(2) {
+            graph.relate::<Child>(pair[0], pair[1]);
+        }
+
+        assert!(
+            graph
+                .relations_traverse_depth::<Child>(chain[0], 0)
+                .eq([chain[0]])
+        );
+        let result = graph
+            .relations_traverse_depth::<Child>(chain[0], 2)
+            .collect::<Vec<_>>();
+        assert_eq!(result, chain[..3]);
+        assert!(!result.contains(&chain[3]));
+        assert_eq!(
+            graph
+                .query::<TraverseDepth<2, Child, AnyIndex>>(chain[1])
+                .collect::<Vec<_>>(),
+            chain[1..4]
+        );
+    }
+
+    #[test]
+    fn test_unrelate_between() {
+        let mut graph = Graph::default();
+        let red = (0..3).map(|_| graph.insert(())).collect::<Vec<_>>();
+        let blue = (0..3).map(|_| graph.insert(())).collect::<Vec<_>>();
+        for a in &red {
+            for b in &blue {
+                graph.relate::<Effect>(*a, *b);
+                graph.relate::<Effect>(*b, *a);
+            }
+        }
+        graph.relate::<Effect>(red[0], red[1]);
+        graph.relate::<Effect>(blue[0], blue[1]);
+        graph.relate::<Child>(red[0], blue[0]);
+
+        let sources = red.iter().copied().collect::<HashSet<_>>();
+        let targets = blue.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(graph.unrelate_between::<Effect>(&sources, &targets), 9);
+        assert!(
+            red.iter()
+                .all(|a| blue.iter().all(|b| !graph.are_related::<Effect>(*a, *b)))
+        );
+        assert!(blue.iter().all(|b| {
+            graph
+                .relations_incoming::<Effect>(*b)
+                .all(|a| !sources.contains(&a))
+        }));
+        assert!(graph.are_related::<Effect>(blue[2], red[2]));
+        assert!(graph.are_related::<Effect>(red[0], red[1]));
+        assert!(graph.are_related::<Effect>(blue[0], blue[1]));
+        assert!(graph.are_related::<Child>(red[0], blue[0]));
+        assert_eq!(graph.relation_count::<Effect>(), 11);
+        assert_eq!(graph.unrelate_between::<Parent>(&sources, &targets), 0);
+    }
+
+    #[test]
+    fn test_relations_traverse_with_depth() {
+        let mut graph = Graph::default();
+        let root = graph.insert(());
+        let a = graph.insert(());
+        let b = graph.insert(());
+        let c = graph.insert(());
+        let d = graph.insert(());
+        let e = graph.insert(());
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, c);
+        graph.relate::<Child>(c, d);
+        graph.relate::<Child>(root, d);
+        graph.relate::<Child>(d, e);
+
+        let depths = graph
+            .relations_traverse_with_depth::<Child>(root)
+            .map(|(depth, index)| (index, depth))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(depths.len(), 6);
+        assert_eq!(depths[&root], 0);
+        assert_eq!(depths[&a], 1);
+        assert_eq!(depths[&b], 1);
+        assert_eq!(depths[&c], 2);
+        assert_eq!(depths[&d], 1);
+        assert_eq!(depths[&e], 2);
+        let order = graph
+            .relations_traverse_with_depth::<Child>(root)
+            .map(|(depth, _)| depth)
+            .collect::<Vec<_>>();
+        assert!(order.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_query_edges() {
+        let mut graph = Graph::default();
+        let tree = graph.insert(Health(10));
+        let other = graph.insert(Health(20));
+        let fire = graph.insert(Position(1, 1));
+        let player = graph.insert(Player);
+        graph.relate::<Effect>(tree, fire);
+        graph.relate::<Effect>(other, fire);
+        graph.relate::<Effect>(player, fire);
+        graph.relate::<Effect>(tree, other);
+        graph.relate::<Child>(tree, fire);
+
+        let mut edges = graph
+            .query_edges::<Effect, Health, Position>()
+            .map(|(from, health, to, position)| (from, health.0, to, position.0))
+            .collect::<Vec<_>>();
+        edges.sort();
+        let mut expected = vec![(tree, 10, fire, 1), (other, 20, fire, 1)];
+        expected.sort();
+        assert_eq!(edges, expected);
+
+        for (_, health, _, mut position) in graph.query_edges_mut::<Effect, Health, Position>() {
+            position.0 += health.0 as i32;
+        }
+        assert_eq!(graph.read::<Position>(fire).unwrap().0, 31);
+
+        for (_, _, _, mut health) in graph.query_edges_mut::<Effect, Health, Health>() {
+            health.0 += 1;
+        }
+        assert_eq!(graph.read::<Health>(other).unwrap().0, 21);
+        assert_eq!(graph.query_edges::<Child, Player, Position>().count(), 0);
+    }
+
+    #[test]
+    fn test_traverse_dfs() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Tree);
+        let a = graph.insert(0usize);
+        let b = graph.insert(1usize);
+        let c = graph.insert(2usize);
+        let d = graph.insert(3usize);
+        graph.relate::<Child>(root, a);
+        graph.relate::<Child>(root, b);
+        graph.relate::<Child>(a, c);
+        graph.relate::<Child>(b, d);
+
+        let bfs = graph.relations_traverse::<Child>(root).collect::<Vec<_>>();
+        assert_eq!(bfs.len(), 5);
+        assert_eq!(bfs[0], root);
+        assert!([a, b].contains(&bfs[1]));
+        assert!([a, b].contains(&bfs[2]));
+
+        let dfs = graph
+            .relations_traverse_dfs::<Child>(root)
+            .collect::<Vec<_>>();
+        assert_eq!(dfs.len(), 5);
+        assert_eq!(dfs[0], root);
+        assert!(
+            graph
+                .relations_outgoing::<Child>(dfs[1])
+                .any(|index| index == dfs[2])
+        );
+        assert!(
+            graph
+                .relations_outgoing::<Child>(dfs[3])
+                .any(|index| index == dfs[4])
+        );
+        assert_eq!(
+            graph
+                .query::<TraverseDfs<Child, AnyIndex>>(root)
+                .collect::<Vec<_>>(),
+            dfs
+        );
+
+        graph.relate::<Child>(c, root);
+        assert_eq!(graph.relations_traverse_dfs::<Child>(root).count(), 5);
+    }
+
+    #[test]
+    fn test_query_with_filter() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Player);
+        let alive = graph.insert(Health(10));
+        let dead = graph.insert(Health(0));
+        let other = graph.insert(Health(5));
+        let tree = graph.insert(Tree);
+        graph.relate::<Child>(root, alive);
+        graph.relate::<Child>(root, dead);
+        graph.relate::<Child>(root, tree);
+        graph.relate::<Effect>(root, other);
+
+        let mut result = graph
+            .query::<Related<Child, AnyIndex>>(root)
+            .with_filter(&graph, |graph, index| {
+                graph
+                    .read::<Health>(index)
+                    .map(|health| health.0 > 0)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(result, vec![alive]);
+
+        assert_eq!(
+            graph
+                .query::<Related<Child, AnyIndex>>(root)
+                .with_filter(&graph, |graph, index| graph.is::<Tree>(index))
+                .collect::<Vec<_>>(),
+            vec![tree]
+        );
+        assert_eq!(
+            graph
+                .query::<Related<Effect, AnyIndex>>(root)
+                .with_filter(&graph, |_, _| false)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_condensation() {
+        let mut graph = Graph::default();
+        let a = graph.insert(0usize);
+        let b = graph.insert(1usize);
+        let c = graph.insert(2usize);
+        let d = graph.insert(3usize);
+        graph.insert(4usize);
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(b, c);
+        graph.relate::<Child>(c, a);
+        graph.relate::<Child>(b, d);
+        graph.relate::<Child>(c, d);
+        graph.relate::<Effect>(d, a);
+
+        let (components, relations) = graph.condensation::<Child>();
+        let mut cycle = vec![a, b, c];
+        cycle.sort();
+        assert_eq!(components, vec![cycle, vec![d]]);
+        assert_eq!(relations, vec![(0, 1)]);
+
+        let (components, relations) = graph.condensation::<Effect>();
+        assert_eq!(components, vec![vec![d], vec![a]]);
+        assert_eq!(relations, vec![(0, 1)]);
+
+        let (components, relations) = graph.condensation::<Parent>();
+        assert!(components.is_empty());
+        assert!(relations.is_empty());
+    }
+
+    #[test]
+    fn test_query_one() {
+        let mut graph = Graph::default();
+        let player = graph.insert(Player);
+        let name = graph.insert("Bob".to_owned());
+        let nameless = graph.insert(Tree);
+        graph.relate::<Attribute>(player, name);
+
+        assert_eq!(
+            graph
+                .query_one::<Related<Attribute, &String>>(player)
+                .unwrap()
+                .as_str(),
+            "Bob"
+        );
+        assert_eq!(
+            graph.query_one::<Related<Attribute, AnyIndex>>(player),
+            Some(name)
+        );
+        assert!(
+            graph
+                .query_one::<Related<Attribute, &String>>(nameless)
+                .is_none()
+        );
+        assert!(
+            graph
+                .query_one::<Related<Attribute, &usize>>(player)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_nodes_grouped_sorted() {
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Tree>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build());
+
+        let mut a = Graph::default();
+        a.insert(Tree);
+        a.insert(Player);
+        a.insert(Tree);
+        a.insert(42usize);
+
+        let mut b = Graph::default();
+        b.insert(42usize);
+        b.insert(Player);
+        b.insert(Tree);
+        b.insert(Tree);
+        let fire = b.insert(Fire);
+        b.remove(fire).unwrap();
+
+        let grouped = a.nodes_grouped_sorted(&registry);
+        assert_eq!(grouped, b.nodes_grouped_sorted(&registry));
+        assert_eq!(
+            grouped
+                .iter()
+                .map(|(name, indices)| (name.as_str(), indices.len()))
+                .collect::<Vec<_>>(),
+            vec![("Player", 1), ("Tree", 2), ("usize", 1)]
+        );
+        assert!(grouped[1].1.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_graph_query_count() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Parent);
+        let fire = graph.insert(Fire);
+        for burning in [true, false, true, true, false] {
+            let tree = graph.insert(Tree);
+            graph.relate::<Child>(root, tree);
+            let health = graph.insert(Health(10));
+            graph.relate::<Child>(tree, health);
+            if burning {
+                graph.relate::<Effect>(tree, fire);
+            }
+        }
+
+        assert_eq!(
+            graph.query_count::<Related<
+                Child,
+                Query<
+                    Node<Tree>,
+                    (
+                        AnyIndex,
+                        Related<Child, &mut Health>,
+                        Related<Effect, Is<Fire>>,
+                    ),
+                >,
+            >>(root),
+            3
+        );
+        assert_eq!(
+            graph.query_count::<Related<Child, Query<Node<Tree>, Related<Effect, Is<Fire>>>>>(root),
+            graph
+                .query::<Related<Child, Query<Node<Tree>, Related<Effect, Is<Fire>>>>>(root)
+                .count()
+        );
+        assert_eq!(
+            graph.query_count::<Related<Child, Query<Node<Tree>, Not<Related<Effect, Is<Fire>>>>>>(
+                root
+            ),
+            2
+        );
+        assert_eq!(graph.query_count::<Related<Effect, AnyIndex>>(fire), 0);
+    }
+
+    #[test]
+    fn test_prefab_to_graph_mapped() {
+        let mut graph = Graph::default();
+        let player = graph.insert(Player);
+        let position = graph.insert(Position(3, -4));
+        let health = graph.insert(Health(7));
+        graph.relate::<Child>(player, position);
+        graph.relate::<Child>(player, health);
+
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Child>().build())
+            .with_type(NativeStructBuilder::new::<Player>().build())
+            .with_type(NativeStructBuilder::new::<Position>().build())
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Child>()
+            .with_serde::<Player>()
+            .with_serde::<Position>()
+            .with_serde::<Health>();
+
+        let prefab = Prefab::from_graph(&graph, &serialization, &registry).unwrap();
+        let (graph, mappings) = prefab
+            .to_graph_mapped(&serialization, &registry, |data_type, data| {
+                if data_type.type_name != "Position" {
+                    return;
+                }
+                if let Intermediate::TupleStruct(fields) = data {
+                    for field in fields {
+                        if let Intermediate::I32(value) = field {
+                            *value *= 2;
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        assert_eq!(
+            *graph.read::<Position>(mappings[&position]).unwrap(),
+            Position(6, -8)
+        );
+        assert_eq!(graph.read::<Health>(mappings[&health]).unwrap().0, 7);
+        assert!(graph.are_related::<Child>(mappings[&player], mappings[&position]));
+    }
+
+    #[test]
+    fn test_query_related_sorted() {
+        let mut graph = Graph::default();
+        let parent = graph.insert(Parent);
+        let c = graph.insert(Position(7, 0));
+        let a = graph.insert(Position(-2, 5));
+        let b = graph.insert(Position(3, 1));
+        let other = graph.insert(Health(1));
+        let effect = graph.insert(Position(0, 0));
+        for child in [c, a, b, other] {
+            graph.relate::<Child>(parent, child);
+        }
+        graph.relate::<Effect>(parent, effect);
+
+        assert_eq!(
+            graph.query_related_sorted::<Child, Position, _>(parent, |position| position.0),
+            vec![a, b, c]
+        );
+        assert_eq!(
+            graph.query_related_sorted::<Child, Position, _>(parent, |position| {
+                std::cmp::Reverse(position.1)
+            }),
+            vec![a, b, c]
+        );
+        assert_eq!(
+            graph.query_related_sorted::<Child, Position, _>(parent, |_| 0),
+            vec![c, a, b]
+        );
+        assert!(
+            graph
+                .query_related_sorted::<Child, Tree, _>(parent, |_| 0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_query_all() {
+        let mut graph = Graph::default();
+        for value in [10, 20, 30] {
+            let tree = graph.insert(Tree);
+            let health = graph.insert(Health(value));
+            graph.relate::<Child>(tree, health);
+        }
+        let player = graph.insert(Player);
+        let health = graph.insert(Health(100));
+        graph.relate::<Child>(player, health);
+
+        let mut result = graph
+            .query_all::<Tree, Related<Child, &Health>>()
+            .map(|health| health.0)
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(result, vec![10, 20, 30]);
+
+        for mut health in graph.query_all::<Tree, Related<Child, &mut Health>>() {
+            health.0 += 1;
+        }
+        assert_eq!(
+            graph
+                .query_all::<Tree, Related<Child, &Health>>()
+                .map(|health| health.0)
+                .sum::<usize>(),
+            63
+        );
+        assert_eq!(graph.read::<Health>(health).unwrap().0, 100);
+        assert_eq!(graph.query_all::<Fire, AnyIndex>().count(), 0);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut graph = Graph::default().with_new_arena_capacity(4);
+        assert_eq!(graph.node_capacity::<Tree>(), 0);
+        graph.reserve::<Tree>(100);
+        let capacity = graph.node_capacity::<Tree>();
+        assert!(capacity >= 100);
+        assert_eq!(graph.count_of::<Tree>(), 0);
+
+        for _ in 0..100 {
+            graph.insert(Tree);
+        }
+        assert_eq!(graph.node_capacity::<Tree>(), capacity);
+        assert_eq!(graph.count_of::<Tree>(), 100);
+
+        let healths = (0..4)
+            .map(|index| graph.insert(Health(index)))
+            .collect::<Vec<_>>();
+        let player = graph.insert(Player);
+        graph.relate::<Effect>(player, healths[1]);
+        assert_eq!(graph.node_capacity::<Health>(), 4);
+        graph.reserve::<Health>(100);
+        assert!(graph.node_capacity::<Health>() >= 104);
+        for (value, index) in healths.iter().enumerate() {
+            assert_eq!(graph.read::<Health>(*index).unwrap().0, value);
+        }
+        assert!(graph.are_related::<Effect>(player, healths[1]));
+
+        graph.remove(healths[0]).unwrap();
+        graph.reserve::<Health>(200);
+        assert!(graph.node_capacity::<Health>() >= 203);
+        assert_eq!(graph.count_of::<Health>(), 3);
+        assert!(!graph.contains(healths[0]));
+        for (value, index) in healths.iter().enumerate().skip(1) {
+            assert_eq!(graph.read::<Health>(*index).unwrap().0, value);
+        }
+        let capacity = graph.node_capacity::<Health>();
+        for index in 0..200 {
+            graph.insert(Health(index));
+        }
+        assert_eq!(graph.node_capacity::<Health>(), capacity);
+        assert!(!graph.contains(healths[0]));
+    }
+
+    #[test]
+    fn test_detach_reattach() {
+        let mut graph = Graph::default();
+        let parent = graph.insert(Parent);
+        let node = graph.insert(Player);
+        let child = graph.insert(Tree);
+        let fire = graph.insert(Fire);
+        let other = graph.insert(Parent);
+        graph.relate::<Child>(parent, node);
+        graph.relate::<Parent>(node, parent);
+        graph.relate::<Child>(node, child);
+        graph.relate::<Effect>(fire, node);
+        graph.relate::<Attribute>(node, node);
+        graph.relate::<Child>(parent, child);
+
+        let relations = |graph: &Graph, index: AnyIndex| {
+            let mut outgoing = graph.relations_outgoing_any(index).collect::<Vec<_>>();
+            let mut incoming = graph.relations_incoming_any(index).collect::<Vec<_>>();
+            outgoing.sort();
+            incoming.sort();
+            (outgoing, incoming)
+        };
+        let before = relations(&graph, node);
+
+        let detached = graph.detach(node);
+        assert_eq!(detached.outgoing.len(), 3);
+        assert_eq!(detached.incoming.len(), 3);
+        assert_eq!(relations(&graph, node), (vec![], vec![]));
+        assert!(graph.is::<Player>(node));
+        assert!(graph.are_related::<Child>(parent, child));
+
+        graph.reattach(node, detached.clone());
+        assert_eq!(relations(&graph, node), before);
+        assert!(graph.are_related::<Child>(parent, node));
+        assert!(graph.are_related::<Effect>(fire, node));
+
+        assert_eq!(graph.detach(node), detached);
+        let detached = graph.detach(parent);
+        graph.reattach(other, detached);
+        assert!(graph.are_related::<Child>(other, child));
+        assert_eq!(graph.relations_outgoing_any(parent).count(), 0);
+
+        let target = graph.insert(Tree);
+        graph.relate::<Effect>(fire, target);
+        let detached = graph.detach(fire);
+        graph.set_relation_cardinality::<Effect>(Some(1), None, CardinalityPolicy::EvictOldest);
+        graph.relate::<Effect>(fire, child);
+        graph.reattach(fire, detached);
+        assert!(graph.are_related::<Effect>(fire, target));
+        assert!(graph.are_related::<Effect>(fire, child));
+    }
+
+    #[test]
+    fn test_with_sibling_count() {
+        let mut graph = Graph::default();
+        let parent = graph.insert(Parent);
+        let children = (0..5)
+            .map(|value| {
+                let child = graph.insert(Position(value, 0));
+                graph.relate::<Child>(parent, child);
+                child
+            })
+            .collect::<Vec<_>>();
+        let effect = graph.insert(Fire);
+        graph.relate::<Effect>(parent, effect);
+
+        let mut result = graph
+            .query::<WithSiblingCount<Child, AnyIndex>>(parent)
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            children.iter().map(|child| (5, *child)).collect::<Vec<_>>()
+        );
+
+        let mut offsets = graph
+            .query::<WithSiblingCount<Child, &Position>>(parent)
+            .map(|(count, position)| position.0 as f32 / count as f32)
+            .collect::<Vec<_>>();
+        offsets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(offsets, vec![0.0, 0.2, 0.4, 0.6, 0.8]);
+
+        assert_eq!(
+            graph
+                .query::<WithSiblingCount<Effect, &Position>>(parent)
+                .count(),
+            0
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_weighted_random_neighbor() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let mut graph = Graph::default();
+        let from = graph.insert(Player);
+        let a = graph.insert(1usize);
+        let b = graph.insert(3usize);
+        let c = graph.insert(0usize);
+        for to in [a, b, c] {
+            graph.relate::<Child>(from, to);
+        }
+        let weight = |graph: &Graph, index: AnyIndex| *graph.read::<usize>(index).unwrap() as f64;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let picks = (0..8)
+            .map(|_| {
+                graph
+                    .weighted_random_neighbor::<Child>(
+                        from,
+                        |index| weight(&graph, index),
+                        &mut rng,
+                    )
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(picks, vec![b, b, b, b, a, b, b, b]);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = HashMap::<AnyIndex, usize>::default();
+        for _ in 0..10000 {
+            let picked = graph
+                .weighted_random_neighbor::<Child>(from, |index| weight(&graph, index), &mut rng)
+                .unwrap();
+            *counts.entry(picked).or_default() += 1;
+        }
+        assert!(!counts.contains_key(&c));
+        assert!((2200..2800).contains(&counts[&a]));
+        assert!((7200..7800).contains(&counts[&b]));
+
+        assert!(
+            graph
+                .weighted_random_neighbor::<Child>(from, |_| 0.0, &mut rng)
+                .is_none()
+        );
+        assert!(
+            graph
+                .weighted_random_neighbor::<Effect>(from, |_| 1.0, &mut rng)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut graph = Graph::default();
+        let player = graph.insert(Player);
+        let controller = graph.insert(Controller { forward: false });
+        graph.relate::<Child>(player, controller);
+        graph.relate::<Parent>(controller, player);
+
+        let old = graph
+            .replace(controller, Controller { forward: true })
+            .unwrap();
+        assert!(!old.forward);
+        assert!(graph.read::<Controller>(controller).unwrap().forward);
+        assert!(graph.are_related::<Child>(player, controller));
+        assert!(graph.are_related::<Parent>(controller, player));
+        assert_eq!(
+            graph
+                .query::<Related<Child, &Controller>>(player)
+                .map(|controller| controller.forward)
+                .collect::<Vec<_>>(),
+            vec![true]
+        );
+
+        assert!(graph.replace(controller, Position(0, 0)).is_err());
+        graph.remove(controller).unwrap();
+        assert!(
+            graph
+                .replace(controller, Controller { forward: false })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_retype() {
+        let mut graph = Graph::default();
+        let parent = graph.insert(Parent);
+        let node = graph.insert(Tree);
+        let child = graph.insert(Player);
+        let fire = graph.insert(Fire);
+        graph.relate::<Child>(parent, node);
+        graph.relate::<Parent>(node, parent);
+        graph.relate::<Child>(node, child);
+        graph.relate::<Effect>(node, fire);
+        graph.relate::<Effect>(fire, node);
+        graph.relate::<Attribute>(node, node);
+        graph.relate_with(fire, node, Position(1, 2));
+        graph.relate_with(node, node, Health(3));
+        graph.add_tag::<Fire>(node);
+
+        let retyped = graph.retype::<Tree, Health>(node, Health(5)).unwrap();
+        assert_ne!(retyped, node);
+        assert!(!graph.contains(node));
+        assert_eq!(graph.read::<Health>(retyped).unwrap().0, 5);
+        assert!(graph.are_related::<Child>(parent, retyped));
+        assert!(graph.are_related::<Parent>(retyped, parent));
+        assert!(graph.are_related::<Child>(retyped, child));
+        assert!(graph.are_related::<Effect>(retyped, fire));
+        assert!(graph.are_related::<Effect>(fire, retyped));
+        assert!(graph.are_related::<Attribute>(retyped, retyped));
+        assert!(graph.has_tag::<Fire>(retyped));
+        assert!(!graph.has_tag::<Fire>(node));
+        assert_eq!(graph.relations_outgoing_any(node).count(), 0);
+        assert_eq!(graph.relations_incoming_any(node).count(), 0);
+        assert_eq!(graph.relations_outgoing_any(retyped).count(), 5);
+        assert_eq!(graph.relations_incoming_any(retyped).count(), 5);
+        assert_eq!(
+            *graph.relation_data::<Position>(fire, retyped).unwrap(),
+            Position(1, 2)
+        );
+        assert_eq!(
+            graph.relation_data::<Health>(retyped, retyped).unwrap().0,
+            3
+        );
+
+        assert!(graph.retype::<Tree, Player>(retyped, Player).is_err());
+        assert!(graph.retype::<Tree, Player>(node, Player).is_err());
+        assert!(graph.is::<Health>(retyped));
+    }
+
+    #[test]
+    fn test_relations_canonical() {
+        let mut graph = Graph::default();
+        let nodes = (0..6).map(|value| graph.insert(value)).collect::<Vec<_>>();
+        for (from, to) in [(5, 1), (0, 3), (2, 2), (4, 0), (1, 5), (3, 4)] {
+            graph.relate::<Child>(nodes[from], nodes[to]);
+        }
+        graph.relate::<Effect>(nodes[3], nodes[1]);
+        graph.relate::<Effect>(nodes[0], nodes[1]);
+        graph.relate::<Parent>(nodes[1], nodes[0]);
+
+        let canonical = graph.relations_canonical();
+        assert_eq!(canonical.len(), 9);
+        assert!(canonical.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            canonical.iter().copied().collect::<HashSet<_>>(),
+            graph.relations().collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            canonical
+                .iter()
+                .filter(|(type_hash, _, _)| *type_hash == TypeHash::of::<Effect>())
+                .map(|(_, from, to)| (*from, *to))
+                .collect::<Vec<_>>(),
+            vec![(nodes[0], nodes[1]), (nodes[3], nodes[1])]
+        );
+        assert!(Graph::default().relations_canonical().is_empty());
+    }
+
+    #[test]
+    fn test_root_of() {
+        let mut graph = Graph::default();
+        let root = graph.insert(Parent);
+        let player = graph.insert(Player);
+        let controller = graph.insert(Controller { forward: true });
+        let tree = graph.insert(Tree);
+        let health = graph.insert(Health(10));
+        let name = graph.insert("Tree".to_owned());
+        graph.relate::<Child>(root, player);
+        graph.relate::<Child>(player, controller);
+        graph.relate::<Child>(root, tree);
+        graph.relate::<Child>(tree, health);
+        graph.relate::<Attribute>(tree, name);
+
+        assert_eq!(graph.root_of::<Child>(health), root);
+        assert_eq!(graph.root_of::<Child>(controller), root);
+        assert_eq!(graph.root_of::<Child>(root), root);
+        assert_eq!(graph.root_of::<Child>(name), name);
+        assert_eq!(graph.root_of::<Attribute>(name), tree);
+
+        let a = graph.insert(0usize);
+        let b = graph.insert(1usize);
+        let c = graph.insert(2usize);
+        graph.relate::<Child>(a, b);
+        graph.relate::<Child>(b, c);
+        graph.relate::<Child>(c, a);
+        assert_eq!(graph.root_of::<Child>(c), a);
+        assert_eq!(graph.root_of::<Child>(a), b);
+
+        let first_parent = graph.insert(Player);
+        let second_parent = graph.insert(Player);
+        let first_root = graph.insert(Parent);
+        let second_root = graph.insert(Parent);
+        let child = graph.insert(Player);
+        graph.relate::<Child>(first_root, first_parent);
+        graph.relate::<Child>(second_root, second_parent);
+        graph.relate::<Child>(second_parent, child);
+        graph.relate::<Child>(first_parent, child);
+        assert!(first_parent < second_parent);
+        for _ in 0..10 {
+            assert_eq!(graph.root_of::<Child>(child), first_root);
+        }
+        graph.unrelate::<Child>(first_parent, child);
+        assert_eq!(graph.root_of::<Child>(child), second_root);
+    }
+
+    #[test]
+    fn test_command_apply_errors() {
+        let registry = Registry::default()
+            .with_basic_types()
+            .with_type(NativeStructBuilder::new::<Health>().build());
+        let serialization = SerializationRegistry::default()
+            .with_basic_types()
+            .with_serde::<Health>();
+
+        let mut graph = Graph::default();
+        graph.start_recording();
+        let GraphCommand::Insert { data_type, .. } =
+            GraphCommand::insert(&Health(1), &serialization, &registry).unwrap()
+        else {
+            unreachable!();
+        };
+        let result = graph.apply(
+            GraphCommand::Insert {
+                data_type: data_type.clone(),
+                data: Intermediate::String("invalid".to_owned()),
+            },
+            &serialization,
+            &registry,
+        );
+        assert!(matches!(
+            result,
+            Err(PrefabError::CouldNotDeserializeType { .. })
+        ));
+        assert_eq!(graph.count_of::<Health>(), 0);
+        assert!(graph.is_empty());
+
+        let missing = PrefabDataType {
+            type_name: "Missing".to_owned(),
+            module_name: None,
+        };
+        let result = graph.apply(
+            GraphCommand::Insert {
+                data_type: missing.clone(),
+                data: Intermediate::Unit,
+            },
+            &serialization,
+            &registry,
+        );
+        assert!(matches!(
+            result,
+            Err(PrefabError::CouldNotFindTypeByName { type_name, .. }) if type_name == "Missing"
+        ));
+
+        let health = graph
+            .apply(
+                GraphCommand::insert(&Health(1), &serialization, &registry).unwrap(),
+                &serialization,
+                &registry,
+            )
+            .unwrap()
+            .unwrap();
+        let node = PrefabRelationsPairItem {
+            data_type,
+            index: health.index(),
+        };
+        let result = graph.apply(
+            GraphCommand::Relate {
+                category: missing,
+                source: node.clone(),
+                target: node,
+            },
+            &serialization,
+            &registry,
+        );
+        assert!(matches!(
+            result,
+            Err(PrefabError::CouldNotFindTypeByName { .. })
+        ));
+        let log = graph.stop_recording();
+        assert_eq!(log.len(), 1);
+        assert_eq!(graph.count_of::<Health>(), 1);
+
+        let mut replayed = Graph::default();
+        for command in log {
+            replayed.apply(command, &serialization, &registry).unwrap();
+        }
+        assert_eq!(replayed.read::<Health>(health).unwrap().0, 1);
+    }
 }
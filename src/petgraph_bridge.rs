@@ -0,0 +1,60 @@
+use crate::graph::Graph;
+use intuicio_framework_arena::AnyIndex;
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
+
+impl Graph {
+    /// Exports topology of the specified relation category as petgraph
+    /// directed graph. Every node becomes petgraph node weighted with its
+    /// index, every relation of that category becomes an edge.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    ///
+    /// # Returns
+    /// A tuple containing petgraph graph and a mapping of node indices to
+    /// petgraph node indices.
+    pub fn to_petgraph<T>(&self) -> (petgraph::Graph<AnyIndex, ()>, HashMap<AnyIndex, NodeIndex>) {
+        let mut indices = self.indices().collect::<Vec<_>>();
+        indices.sort();
+        let mut result = petgraph::Graph::with_capacity(indices.len(), self.relation_count::<T>());
+        let mappings = indices
+            .into_iter()
+            .map(|index| (index, result.add_node(index)))
+            .collect::<HashMap<_, _>>();
+        let mut edges = self.relations_outgoing_all::<T>().collect::<Vec<_>>();
+        edges.sort();
+        for (from, to) in edges {
+            if let (Some(from), Some(to)) = (mappings.get(&from), mappings.get(&to)) {
+                result.add_edge(*from, *to, ());
+            }
+        }
+        (result, mappings)
+    }
+
+    /// Imports edges of petgraph directed graph as relations of the specified
+    /// relation category. Node weights are used as indices of related nodes,
+    /// edges between nodes missing in this graph are skipped.
+    ///
+    /// # Arguments
+    /// * `graph` - The petgraph graph to import edges from.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the relation category.
+    /// * `E` - The type of petgraph edge weights.
+    ///
+    /// # Returns
+    /// The number of imported relations.
+    pub fn relate_from_petgraph<T, E>(&mut self, graph: &petgraph::Graph<AnyIndex, E>) -> usize {
+        let mut count = 0;
+        for edge in graph.raw_edges() {
+            let from = graph[edge.source()];
+            let to = graph[edge.target()];
+            if self.contains(from) && self.contains(to) {
+                self.relate::<T>(from, to);
+                count += 1;
+            }
+        }
+        count
+    }
+}
@@ -1,14 +1,26 @@
 use crate::{graph::Graph, relations::RelationsTable};
-use intuicio_core::{registry::Registry, types::TypeQuery};
-use intuicio_data::type_hash::TypeHash;
+use intuicio_core::{
+    registry::Registry,
+    types::{Type, TypeQuery},
+};
+use intuicio_data::{non_zero_alloc, non_zero_dealloc, type_hash::TypeHash};
 use intuicio_framework_arena::{AnyArena, AnyIndex, ArenaError, Index};
 use intuicio_framework_serde::{Intermediate, SerializationRegistry};
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeSeed};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    hash::Hash,
+    mem::MaybeUninit,
+};
 
 #[derive(Debug)]
 pub enum PrefabError {
     CouldNotFindType(TypeHash),
+    CouldNotFindTypeByName {
+        type_name: String,
+        module_name: Option<String>,
+    },
     CouldNotSerializeType {
         type_name: String,
         module_name: Option<String>,
@@ -27,6 +39,15 @@ impl std::fmt::Display for PrefabError {
             Self::CouldNotFindType(type_hash) => {
                 write!(f, "Could not find type by hash: {type_hash:?}")
             }
+            Self::CouldNotFindTypeByName {
+                type_name,
+                module_name,
+            } => write!(
+                f,
+                "Could not find type by name: {}::{}",
+                module_name.as_deref().unwrap_or_default(),
+                type_name
+            ),
             Self::CouldNotSerializeType {
                 type_name,
                 module_name,
@@ -65,7 +86,7 @@ impl From<Box<dyn Error>> for PrefabError {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct PrefabDataType {
     pub type_name: String,
     pub module_name: Option<String>,
@@ -88,6 +109,8 @@ pub struct PrefabRelationsPairItem {
 pub struct PrefabRelationsPair {
     pub source: PrefabRelationsPairItem,
     pub target: PrefabRelationsPairItem,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Intermediate>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -107,6 +130,8 @@ pub struct Prefab {
 
 impl Prefab {
     /// Creates a new prefab from a graph.
+    /// Relation payloads are serialized along with relations, so relation
+    /// categories that hold payloads have to be registered for serialization.
     ///
     /// # Arguments
     /// * `graph` - The graph to create the prefab from.
@@ -120,7 +145,7 @@ impl Prefab {
         serialization: &SerializationRegistry,
         registry: &Registry,
     ) -> Result<Self, PrefabError> {
-        let nodes = graph
+        let mut nodes = graph
             .nodes
             .arenas()
             .iter()
@@ -155,7 +180,7 @@ impl Prefab {
                 })
             })
             .collect::<Result<Vec<_>, PrefabError>>()?;
-        let relations = graph
+        let mut relations = graph
             .relations
             .iter()
             .map(|(type_hash, table)| {
@@ -170,7 +195,9 @@ impl Prefab {
                     module_name: type_.module_name().map(|name| name.to_owned()),
                 };
                 let mut pairs = Vec::<PrefabRelationsPair>::default();
-                for (source, target) in table.iter_outgoing() {
+                let mut edges = table.iter_outgoing().collect::<Vec<_>>();
+                edges.sort();
+                for (source, target) in edges {
                     let source_type = registry
                         .find_type(TypeQuery {
                             type_hash: Some(source.type_hash()),
@@ -200,14 +227,171 @@ impl Prefab {
                             data_type: target_data_type,
                             index: target.index(),
                         },
+                        payload: serialize_payload(
+                            table,
+                            source,
+                            target,
+                            &type_,
+                            serialization,
+                            registry,
+                        )?,
                     });
                 }
                 Ok(PrefabRelationArchetype { data_type, pairs })
             })
             .collect::<Result<Vec<_>, PrefabError>>()?;
+        nodes.sort_by(|a, b| a.data_type.cmp(&b.data_type));
+        relations.sort_by(|a, b| a.data_type.cmp(&b.data_type));
         Ok(Self { nodes, relations })
     }
 
+    /// Computes hash of prefab content, that depends only on node data and
+    /// relations topology, not on node indices or insertion order.
+    /// Node labels are refined iteratively with labels of their neighbors,
+    /// so structurally equal graphs produce equal hashes.
+    ///
+    /// Hash is computed with 64-bit FNV-1a over canonical byte encoding of
+    /// type names and serialized node data, so it stays the same across
+    /// builds, platforms and crate versions with the same major version,
+    /// as long as serialized form of node data does not change.
+    ///
+    /// # Returns
+    /// The content hash.
+    pub fn content_hash(&self) -> u64 {
+        fn hash_of(write: impl FnOnce(&mut ContentHasher)) -> u64 {
+            let mut hasher = ContentHasher::default();
+            write(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut ids = HashMap::<(&PrefabDataType, Index), usize>::default();
+        let mut labels = Vec::<u64>::default();
+        for archetype in &self.nodes {
+            for (index, data) in archetype.indices.iter().zip(archetype.data.iter()) {
+                ids.insert((&archetype.data_type, *index), labels.len());
+                labels.push(hash_of(|hasher| {
+                    hasher.write_data_type(&archetype.data_type);
+                    hasher.write_intermediate(data);
+                }));
+            }
+        }
+        let mut outgoing = vec![Vec::<(u64, usize)>::default(); labels.len()];
+        let mut incoming = vec![Vec::<(u64, usize)>::default(); labels.len()];
+        let mut edges = Vec::<(u64, usize, usize)>::default();
+        for archetype in &self.relations {
+            let category = hash_of(|hasher| hasher.write_data_type(&archetype.data_type));
+            for pair in &archetype.pairs {
+                let source = ids.get(&(&pair.source.data_type, pair.source.index));
+                let target = ids.get(&(&pair.target.data_type, pair.target.index));
+                if let (Some(source), Some(target)) = (source, target) {
+                    outgoing[*source].push((category, *target));
+                    incoming[*target].push((category, *source));
+                    edges.push((category, *source, *target));
+                }
+            }
+        }
+        let mut distinct = labels.iter().collect::<HashSet<_>>().len();
+        for _ in 0..labels.len() {
+            let next = (0..labels.len())
+                .map(|id| {
+                    let mut outgoing = outgoing[id]
+                        .iter()
+                        .map(|(category, target)| (*category, labels[*target]))
+                        .collect::<Vec<_>>();
+                    let mut incoming = incoming[id]
+                        .iter()
+                        .map(|(category, source)| (*category, labels[*source]))
+                        .collect::<Vec<_>>();
+                    outgoing.sort();
+                    incoming.sort();
+                    hash_of(|hasher| {
+                        hasher.write_u64(labels[id]);
+                        hasher.write_pairs(&outgoing);
+                        hasher.write_pairs(&incoming);
+                    })
+                })
+                .collect::<Vec<_>>();
+            let next_distinct = next.iter().collect::<HashSet<_>>().len();
+            labels = next;
+            if next_distinct == distinct {
+                break;
+            }
+            distinct = next_distinct;
+        }
+        let mut edges = edges
+            .into_iter()
+            .map(|(category, source, target)| (category, labels[source], labels[target]))
+            .collect::<Vec<_>>();
+        edges.sort();
+        labels.sort();
+        hash_of(|hasher| {
+            hasher.write_len(labels.len());
+            for label in labels {
+                hasher.write_u64(label);
+            }
+            hasher.write_len(edges.len());
+            for (category, source, target) in edges {
+                hasher.write_u64(category);
+                hasher.write_u64(source);
+                hasher.write_u64(target);
+            }
+        })
+    }
+
+    /// Deserializes only nodes of the specified type, without building the
+    /// whole graph.
+    ///
+    /// # Arguments
+    /// * `serialization` - The serialization registry to use for deserialization.
+    /// * `registry` - The registry to use for type lookups.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of the nodes to read.
+    ///
+    /// # Returns
+    /// A result containing the prefab indices and values of the nodes, or an error.
+    pub fn read_nodes<T: 'static>(
+        &self,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+    ) -> Result<Vec<(Index, T)>, PrefabError> {
+        let type_hash = TypeHash::of::<T>();
+        let type_ = registry
+            .find_type(TypeQuery {
+                type_hash: Some(type_hash),
+                ..Default::default()
+            })
+            .ok_or(PrefabError::CouldNotFindType(type_hash))?;
+        let Some(archetype) = self.nodes.iter().find(|archetype| {
+            archetype.data_type.type_name == type_.name()
+                && archetype.data_type.module_name.as_deref() == type_.module_name()
+        }) else {
+            return Ok(Default::default());
+        };
+        archetype
+            .indices
+            .iter()
+            .zip(archetype.data.iter())
+            .map(|(index, data)| unsafe {
+                let mut value = MaybeUninit::<T>::uninit();
+                let memory = value.as_mut_ptr().cast::<u8>();
+                let initialized = type_.initialize(memory.cast::<_>());
+                serialization
+                    .dynamic_deserialize_to(type_hash, memory, data, initialized, registry)
+                    .map_err(|_| {
+                        if initialized {
+                            type_.finalize(memory.cast::<_>());
+                        }
+                        PrefabError::CouldNotDeserializeType {
+                            type_name: type_.name().to_owned(),
+                            module_name: type_.module_name().map(|name| name.to_owned()),
+                        }
+                    })?;
+                Ok((*index, value.assume_init()))
+            })
+            .collect()
+    }
+
     /// Converts the prefab into a graph.
     ///
     /// # Arguments
@@ -220,6 +404,38 @@ impl Prefab {
         &self,
         serialization: &SerializationRegistry,
         registry: &Registry,
+    ) -> Result<(Graph, HashMap<AnyIndex, AnyIndex>), PrefabError> {
+        self.to_graph_inner(
+            serialization,
+            registry,
+            None::<fn(&PrefabDataType, &mut Intermediate)>,
+        )
+    }
+
+    /// Converts the prefab into a graph, letting caller patch serialized data
+    /// of each node before it gets deserialized, e.g. to migrate old fields.
+    ///
+    /// # Arguments
+    /// * `serialization` - The serialization registry to use for deserialization.
+    /// * `registry` - The registry to use for type lookups.
+    /// * `map` - The function called with data type and serialized data of each node.
+    ///
+    /// # Returns
+    /// A result containing the graph and a mapping of old indices to new indices.
+    pub fn to_graph_mapped(
+        &self,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+        map: impl Fn(&PrefabDataType, &mut Intermediate),
+    ) -> Result<(Graph, HashMap<AnyIndex, AnyIndex>), PrefabError> {
+        self.to_graph_inner(serialization, registry, Some(map))
+    }
+
+    fn to_graph_inner(
+        &self,
+        serialization: &SerializationRegistry,
+        registry: &Registry,
+        map: Option<impl Fn(&PrefabDataType, &mut Intermediate)>,
     ) -> Result<(Graph, HashMap<AnyIndex, AnyIndex>), PrefabError> {
         let mut mappings = HashMap::<AnyIndex, AnyIndex>::default();
         let mut nodes = AnyArena::default();
@@ -243,6 +459,16 @@ impl Prefab {
                     nodes.ensure_arena_raw(type_.type_hash(), *type_.layout(), type_.finalizer())
                 };
                 for (old_index, data) in archetype.indices.iter().zip(archetype.data.iter()) {
+                    let patched;
+                    let data = match &map {
+                        Some(map) => {
+                            let mut data = data.clone();
+                            map(&archetype.data_type, &mut data);
+                            patched = data;
+                            &patched
+                        }
+                        None => data,
+                    };
                     let (new_index, memory) = arena.allocate();
                     type_.initialize(memory.cast::<_>());
                     serialization
@@ -323,10 +549,354 @@ impl Prefab {
                         })
                     })?;
                     table.insert(source, target);
+                    if let Some(payload) = &pair.payload {
+                        deserialize_payload(
+                            &mut table,
+                            source,
+                            target,
+                            &type_,
+                            payload,
+                            serialization,
+                            registry,
+                        )?;
+                    }
                 }
                 Ok((type_.type_hash(), table))
             })
             .collect::<Result<HashMap<_, _>, PrefabError>>()?;
-        Ok((Graph { nodes, relations }, mappings))
+        Ok((
+            Graph {
+                nodes,
+                relations,
+                ..Default::default()
+            },
+            mappings,
+        ))
+    }
+}
+
+/// Deserializes data of specified type into newly allocated buffer, so
+/// failed deserialization never leaves partially initialized value in arena.
+/// Caller takes ownership of the buffer and has to deallocate it.
+///
+/// # Safety
+/// Returned buffer holds initialized value of specified type.
+pub(crate) unsafe fn deserialize_to_buffer(
+    type_: &Type,
+    data: &Intermediate,
+    serialization: &SerializationRegistry,
+    registry: &Registry,
+) -> Result<*mut u8, PrefabError> {
+    unsafe {
+        let layout = *type_.layout();
+        let buffer = non_zero_alloc(layout);
+        let initialized = type_.initialize(buffer.cast::<_>());
+        if serialization
+            .dynamic_deserialize_to(type_.type_hash(), buffer, data, initialized, registry)
+            .is_err()
+        {
+            if initialized {
+                type_.finalize(buffer.cast::<_>());
+            }
+            non_zero_dealloc(buffer, layout);
+            return Err(PrefabError::CouldNotDeserializeType {
+                type_name: type_.name().to_owned(),
+                module_name: type_.module_name().map(|name| name.to_owned()),
+            });
+        }
+        Ok(buffer)
+    }
+}
+
+/// Serializes payload of relation, if it has any.
+pub(crate) fn serialize_payload(
+    relations: &RelationsTable,
+    from: AnyIndex,
+    to: AnyIndex,
+    type_: &Type,
+    serialization: &SerializationRegistry,
+    registry: &Registry,
+) -> Result<Option<Intermediate>, PrefabError> {
+    let Some(data) = relations.payload_ptr(from, to) else {
+        return Ok(None);
+    };
+    unsafe { serialization.dynamic_serialize_from(type_.type_hash(), data, registry) }
+        .map(Some)
+        .map_err(|_| PrefabError::CouldNotSerializeType {
+            type_name: type_.name().to_owned(),
+            module_name: type_.module_name().map(|name| name.to_owned()),
+        })
+}
+
+/// Deserializes payload of relation and stores it in relations table.
+pub(crate) fn deserialize_payload(
+    relations: &mut RelationsTable,
+    from: AnyIndex,
+    to: AnyIndex,
+    type_: &Type,
+    data: &Intermediate,
+    serialization: &SerializationRegistry,
+    registry: &Registry,
+) -> Result<(), PrefabError> {
+    unsafe {
+        let buffer = deserialize_to_buffer(type_, data, serialization, registry)?;
+        let layout = *type_.layout();
+        relations.set_payload_raw(
+            from,
+            to,
+            type_.type_hash(),
+            layout,
+            type_.finalizer(),
+            buffer,
+        );
+        non_zero_dealloc(buffer, layout);
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a hasher with canonical encoding of values, used by
+/// `Prefab::content_hash` so hashes do not depend on platform or compiler.
+struct ContentHasher(u64);
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl ContentHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write_u64(len as u64);
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_len(value.len());
+        self.write(value.as_bytes());
+    }
+
+    fn write_data_type(&mut self, data_type: &PrefabDataType) {
+        self.write_str(&data_type.type_name);
+        match &data_type.module_name {
+            Some(module_name) => {
+                self.write_u8(1);
+                self.write_str(module_name);
+            }
+            None => self.write_u8(0),
+        }
+    }
+
+    fn write_pairs(&mut self, pairs: &[(u64, u64)]) {
+        self.write_len(pairs.len());
+        for (category, label) in pairs {
+            self.write_u64(*category);
+            self.write_u64(*label);
+        }
+    }
+
+    fn write_fields(&mut self, fields: &[(String, Intermediate)]) {
+        self.write_len(fields.len());
+        for (name, value) in fields {
+            self.write_str(name);
+            self.write_intermediate(value);
+        }
+    }
+
+    fn write_items(&mut self, items: &[Intermediate]) {
+        self.write_len(items.len());
+        for item in items {
+            self.write_intermediate(item);
+        }
+    }
+
+    fn write_intermediate(&mut self, value: &Intermediate) {
+        match value {
+            Intermediate::Unit => self.write_u8(0),
+            Intermediate::Bool(value) => {
+                self.write_u8(1);
+                self.write_u8(*value as u8);
+            }
+            Intermediate::I8(value) => {
+                self.write_u8(2);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::I16(value) => {
+                self.write_u8(3);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::I32(value) => {
+                self.write_u8(4);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::I64(value) => {
+                self.write_u8(5);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::I128(value) => {
+                self.write_u8(6);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::U8(value) => {
+                self.write_u8(7);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::U16(value) => {
+                self.write_u8(8);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::U32(value) => {
+                self.write_u8(9);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::U64(value) => {
+                self.write_u8(10);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::U128(value) => {
+                self.write_u8(11);
+                self.write(&value.to_le_bytes());
+            }
+            Intermediate::F32(value) => {
+                self.write_u8(12);
+                self.write(&value.to_bits().to_le_bytes());
+            }
+            Intermediate::F64(value) => {
+                self.write_u8(13);
+                self.write(&value.to_bits().to_le_bytes());
+            }
+            Intermediate::Char(value) => {
+                self.write_u8(14);
+                self.write(&(*value as u32).to_le_bytes());
+            }
+            Intermediate::String(value) => {
+                self.write_u8(15);
+                self.write_str(value);
+            }
+            Intermediate::Bytes(value) => {
+                self.write_u8(16);
+                self.write_len(value.len());
+                self.write(value);
+            }
+            Intermediate::Option(value) => {
+                self.write_u8(17);
+                match value {
+                    Some(value) => {
+                        self.write_u8(1);
+                        self.write_intermediate(value);
+                    }
+                    None => self.write_u8(0),
+                }
+            }
+            Intermediate::UnitStruct => self.write_u8(18),
+            Intermediate::UnitVariant(name) => {
+                self.write_u8(19);
+                self.write_str(name);
+            }
+            Intermediate::NewTypeStruct(value) => {
+                self.write_u8(20);
+                self.write_intermediate(value);
+            }
+            Intermediate::NewTypeVariant(name, value) => {
+                self.write_u8(21);
+                self.write_str(name);
+                self.write_intermediate(value);
+            }
+            Intermediate::Seq(items) => {
+                self.write_u8(22);
+                self.write_items(items);
+            }
+            Intermediate::Tuple(items) => {
+                self.write_u8(23);
+                self.write_items(items);
+            }
+            Intermediate::TupleStruct(items) => {
+                self.write_u8(24);
+                self.write_items(items);
+            }
+            Intermediate::TupleVariant(name, items) => {
+                self.write_u8(25);
+                self.write_str(name);
+                self.write_items(items);
+            }
+            Intermediate::Map(entries) => {
+                // Map entries order is not canonical, so entries are hashed
+                // separately and written in sorted order.
+                let mut entries = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut hasher = Self::default();
+                        hasher.write_intermediate(key);
+                        hasher.write_intermediate(value);
+                        hasher.finish()
+                    })
+                    .collect::<Vec<_>>();
+                entries.sort();
+                self.write_u8(26);
+                self.write_len(entries.len());
+                for entry in entries {
+                    self.write_u64(entry);
+                }
+            }
+            Intermediate::Struct(fields) => {
+                self.write_u8(27);
+                self.write_fields(fields);
+            }
+            Intermediate::StructVariant(name, fields) => {
+                self.write_u8(28);
+                self.write_str(name);
+                self.write_fields(fields);
+            }
+        }
+    }
+}
+
+/// Serializable snapshot of a graph, carrying registries needed to serialize
+/// its nodes. Graph is serialized in prefab form.
+pub struct GraphSnapshot<'a> {
+    pub graph: &'a Graph,
+    pub serialization: &'a SerializationRegistry,
+    pub registry: &'a Registry,
+}
+
+impl Serialize for GraphSnapshot<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Prefab::from_graph(self.graph, self.serialization, self.registry)
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// Deserialization seed producing a graph from its serialized snapshot.
+pub struct GraphSnapshotSeed<'a> {
+    pub serialization: &'a SerializationRegistry,
+    pub registry: &'a Registry,
+}
+
+impl<'de> DeserializeSeed<'de> for GraphSnapshotSeed<'_> {
+    type Value = Graph;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        Prefab::deserialize(deserializer)?
+            .to_graph(self.serialization, self.registry)
+            .map(|(graph, _)| graph)
+            .map_err(serde::de::Error::custom)
     }
 }
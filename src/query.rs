@@ -1,28 +1,54 @@
 use crate::graph::Graph;
 use intuicio_data::lifetime::{ValueReadAccess, ValueWriteAccess};
 use intuicio_framework_arena::AnyIndex;
-use std::marker::PhantomData;
+use std::{collections::HashSet, iter::FusedIterator, marker::PhantomData};
 
 pub struct QueryIter<'a, Fetch: QueryFetch<'a>> {
     access: Fetch::Access,
+    done: bool,
 }
 
 impl<'a, Fetch: QueryFetch<'a>> QueryIter<'a, Fetch> {
     pub fn new(graph: &'a Graph, index: AnyIndex) -> Self {
         Self {
             access: Fetch::access(graph, index),
+            done: false,
         }
     }
 }
 
+impl<'a, Fetch: QueryFetch<'a, Value = AnyIndex>> QueryIter<'a, Fetch> {
+    /// Filters fetched node indices with closure inspecting node data.
+    /// Filter applies to final query values, so to filter nodes related to
+    /// queried node, query `Related<T, AnyIndex>` and filter its results.
+    pub fn with_filter(
+        self,
+        graph: &'a Graph,
+        filter: impl Fn(&Graph, AnyIndex) -> bool + 'a,
+    ) -> impl FusedIterator<Item = AnyIndex> + 'a
+    where
+        Fetch: 'a,
+        Fetch::Access: 'a,
+    {
+        self.filter(move |index| filter(graph, *index))
+    }
+}
+
 impl<'a, Fetch: QueryFetch<'a>> Iterator for QueryIter<'a, Fetch> {
     type Item = Fetch::Value;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Fetch::fetch(&mut self.access)
+        if self.done {
+            return None;
+        }
+        let result = Fetch::fetch(&mut self.access);
+        self.done = result.is_none();
+        result
     }
 }
 
+impl<'a, Fetch: QueryFetch<'a>> FusedIterator for QueryIter<'a, Fetch> {}
+
 pub trait QueryFetch<'a> {
     type Value;
     type Access;
@@ -72,6 +98,51 @@ impl<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
     }
 }
 
+pub struct WithSiblingCount<'a, R, Transform: QueryTransform<'a, Input = AnyIndex>>(
+    PhantomData<fn() -> &'a (R, Transform)>,
+);
+
+impl<'a, R, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
+    for WithSiblingCount<'a, R, Transform>
+{
+    type Value = (usize, Transform::Output);
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        let count = graph.out_degree::<R>(index);
+        Box::new(graph.relations_outgoing::<R>(index).flat_map(move |index| {
+            Transform::transform(graph, index).map(move |output| (count, output))
+        }))
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
+pub struct RelatedIncoming<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>>(
+    PhantomData<fn() -> &'a (T, Transform)>,
+);
+
+impl<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
+    for RelatedIncoming<'a, T, Transform>
+{
+    type Value = Transform::Output;
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        Box::new(
+            graph
+                .relations_incoming::<T>(index)
+                .flat_map(|index| Transform::transform(graph, index)),
+        )
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
 pub struct Traverse<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>>(
     PhantomData<fn() -> &'a (T, Transform)>,
 );
@@ -95,6 +166,143 @@ impl<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
     }
 }
 
+pub struct TraverseDfs<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>>(
+    PhantomData<fn() -> &'a (T, Transform)>,
+);
+
+impl<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
+    for TraverseDfs<'a, T, Transform>
+{
+    type Value = Transform::Output;
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        Box::new(
+            graph
+                .relations_traverse_dfs::<T>(index)
+                .flat_map(|index| Transform::transform(graph, index)),
+        )
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
+pub struct TraverseDepth<'a, const MAX: usize, T, Transform: QueryTransform<'a, Input = AnyIndex>>(
+    PhantomData<fn() -> &'a (T, Transform)>,
+);
+
+impl<'a, const MAX: usize, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
+    for TraverseDepth<'a, MAX, T, Transform>
+{
+    type Value = Transform::Output;
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        Box::new(
+            graph
+                .relations_traverse_depth::<T>(index, MAX)
+                .flat_map(|index| Transform::transform(graph, index)),
+        )
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
+/// Yields nodes reachable with relation category `T` in topological order.
+/// If reachable relations contain a cycle, nodes are yielded in traversal
+/// order instead; use `TryTraverseTopological` to detect that case.
+pub struct TraverseTopological<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>>(
+    PhantomData<fn() -> &'a (T, Transform)>,
+);
+
+impl<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
+    for TraverseTopological<'a, T, Transform>
+{
+    type Value = Transform::Output;
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        let order = graph
+            .relations_traverse_topological::<T>(index)
+            .unwrap_or_else(|order| order);
+        Box::new(
+            order
+                .into_iter()
+                .flat_map(|index| Transform::transform(graph, index)),
+        )
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
+/// Like `TraverseTopological`, but yields `Ok` results when nodes are in
+/// topological order and `Err` results when reachable relations contain a
+/// cycle and nodes fall back to traversal order.
+pub struct TryTraverseTopological<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>>(
+    PhantomData<fn() -> &'a (T, Transform)>,
+);
+
+impl<'a, T, Transform: QueryTransform<'a, Input = AnyIndex>> QueryFetch<'a>
+    for TryTraverseTopological<'a, T, Transform>
+{
+    type Value = Result<Transform::Output, Transform::Output>;
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        match graph.relations_traverse_topological::<T>(index) {
+            Ok(order) => Box::new(
+                order
+                    .into_iter()
+                    .flat_map(|index| Transform::transform(graph, index).map(Ok)),
+            ),
+            Err(order) => Box::new(
+                order
+                    .into_iter()
+                    .flat_map(|index| Transform::transform(graph, index).map(Err)),
+            ),
+        }
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
+pub struct DistinctByIndex<'a, Fetch, Transform>(PhantomData<fn() -> &'a (Fetch, Transform)>)
+where
+    Fetch: QueryFetch<'a, Value = AnyIndex>,
+    Transform: QueryTransform<'a, Input = AnyIndex>;
+
+impl<'a, Fetch, Transform> QueryFetch<'a> for DistinctByIndex<'a, Fetch, Transform>
+where
+    Fetch: QueryFetch<'a, Value = AnyIndex>,
+    Fetch::Access: 'a,
+    Transform: QueryTransform<'a, Input = AnyIndex>,
+{
+    type Value = Transform::Output;
+    type Access = Box<dyn Iterator<Item = Self::Value> + 'a>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        let mut visited = HashSet::<AnyIndex>::default();
+        Box::new(
+            graph
+                .query::<Fetch>(index)
+                .filter(move |index| visited.insert(*index))
+                .flat_map(|index| Transform::transform(graph, index)),
+        )
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.next()
+    }
+}
+
 pub struct Node<T>(PhantomData<fn() -> T>);
 
 impl<T> QueryTransform<'_> for Node<T> {
@@ -128,6 +336,124 @@ impl<T> QueryTransform<'_> for IsNot<T> {
     }
 }
 
+pub struct Not<Fetch>(PhantomData<fn() -> Fetch>);
+
+impl<'a, Fetch: QueryFetch<'a>> QueryFetch<'a> for Not<Fetch> {
+    type Value = ();
+    type Access = Option<()>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        let mut access = Fetch::access(graph, index);
+        Fetch::fetch(&mut access).is_none().then_some(())
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.take()
+    }
+}
+
+pub struct Tagged<Tag>(PhantomData<fn() -> Tag>);
+
+impl<'a, Tag> QueryFetch<'a> for Tagged<Tag> {
+    type Value = ();
+    type Access = Option<()>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        graph.has_tag::<Tag>(index).then_some(())
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.take()
+    }
+}
+
+impl<Tag> QueryTransform<'_> for Tagged<Tag> {
+    type Input = AnyIndex;
+    type Output = ();
+
+    fn transform(graph: &Graph, input: Self::Input) -> impl Iterator<Item = Self::Output> {
+        graph.has_tag::<Tag>(input).then_some(()).into_iter()
+    }
+}
+
+pub struct Untagged<Tag>(PhantomData<fn() -> Tag>);
+
+impl<'a, Tag> QueryFetch<'a> for Untagged<Tag> {
+    type Value = ();
+    type Access = Option<()>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        (!graph.has_tag::<Tag>(index)).then_some(())
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.take()
+    }
+}
+
+impl<Tag> QueryTransform<'_> for Untagged<Tag> {
+    type Input = AnyIndex;
+    type Output = ();
+
+    fn transform(graph: &Graph, input: Self::Input) -> impl Iterator<Item = Self::Output> {
+        (!graph.has_tag::<Tag>(input)).then_some(()).into_iter()
+    }
+}
+
+pub struct DegreeAtLeast<R, const N: usize>(PhantomData<fn() -> R>);
+
+impl<R, const N: usize> DegreeAtLeast<R, N> {
+    fn test(graph: &Graph, index: AnyIndex) -> bool {
+        graph.relations_outgoing::<R>(index).take(N).count() == N
+    }
+}
+
+impl<'a, R, const N: usize> QueryFetch<'a> for DegreeAtLeast<R, N> {
+    type Value = ();
+    type Access = Option<()>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        Self::test(graph, index).then_some(())
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.take()
+    }
+}
+
+impl<R, const N: usize> QueryTransform<'_> for DegreeAtLeast<R, N> {
+    type Input = AnyIndex;
+    type Output = ();
+
+    fn transform(graph: &Graph, input: Self::Input) -> impl Iterator<Item = Self::Output> {
+        Self::test(graph, input).then_some(()).into_iter()
+    }
+}
+
+pub struct Count<'a, R>(PhantomData<fn() -> &'a R>);
+
+impl<'a, R> QueryFetch<'a> for Count<'a, R> {
+    type Value = usize;
+    type Access = Option<usize>;
+
+    fn access(graph: &'a Graph, index: AnyIndex) -> Self::Access {
+        Some(graph.out_degree::<R>(index))
+    }
+
+    fn fetch(access: &mut Self::Access) -> Option<Self::Value> {
+        access.take()
+    }
+}
+
+impl<'a, R> QueryTransform<'a> for Count<'a, R> {
+    type Input = AnyIndex;
+    type Output = usize;
+
+    fn transform(graph: &'a Graph, input: Self::Input) -> impl Iterator<Item = Self::Output> {
+        std::iter::once(graph.out_degree::<R>(input))
+    }
+}
+
 pub struct Copied<T: Copy>(PhantomData<fn() -> T>);
 
 impl<T: Copy> QueryTransform<'_> for Copied<T> {
@@ -264,6 +590,25 @@ impl<'a, T> QueryTransform<'a> for Option<&'a mut T> {
     }
 }
 
+pub trait QueryPredicate<T> {
+    fn test(value: &T) -> bool;
+}
+
+pub struct ReadWhere<'a, T, P: QueryPredicate<T>>(PhantomData<fn() -> &'a (T, P)>);
+
+impl<'a, T, P: QueryPredicate<T>> QueryTransform<'a> for ReadWhere<'a, T, P> {
+    type Input = AnyIndex;
+    type Output = ValueReadAccess<'a, T>;
+
+    fn transform(graph: &'a Graph, input: Self::Input) -> impl Iterator<Item = Self::Output> {
+        graph
+            .read::<T>(input)
+            .ok()
+            .filter(|value| P::test(value))
+            .into_iter()
+    }
+}
+
 pub struct Limit<'a, const COUNT: usize, Transform: QueryTransform<'a>>(
     PhantomData<fn() -> &'a Transform>,
 );
@@ -290,6 +635,29 @@ impl<'a, Transform: QueryTransform<'a>> QueryTransform<'a> for Single<'a, Transf
     }
 }
 
+/// Yields results of `A` transform, falling back to results of `B` transform
+/// only when `A` yields nothing for given input.
+pub struct Or<'a, A, B>(PhantomData<fn() -> &'a (A, B)>)
+where
+    A: QueryTransform<'a>,
+    B: QueryTransform<'a, Input = A::Input, Output = A::Output>;
+
+impl<'a, A, B> QueryTransform<'a> for Or<'a, A, B>
+where
+    A: QueryTransform<'a>,
+    A::Input: Clone,
+    B: QueryTransform<'a, Input = A::Input, Output = A::Output>,
+{
+    type Input = A::Input;
+    type Output = A::Output;
+
+    fn transform(graph: &'a Graph, input: Self::Input) -> impl Iterator<Item = Self::Output> {
+        let mut first = A::transform(graph, input.clone()).peekable();
+        let fallback = first.peek().is_none().then(|| B::transform(graph, input));
+        first.chain(fallback.into_iter().flatten())
+    }
+}
+
 pub struct Query<'a, Transform, Fetch>(PhantomData<fn() -> &'a (Transform, Fetch)>)
 where
     Transform: QueryTransform<'a, Input = AnyIndex, Output = AnyIndex>,
@@ -1,37 +1,261 @@
-use intuicio_framework_arena::AnyIndex;
-use std::collections::{HashMap, HashSet};
+use intuicio_data::{
+    lifetime::{ValueReadAccess, ValueWriteAccess},
+    type_hash::TypeHash,
+};
+use intuicio_framework_arena::{AnyIndex, Arena, Index};
+use std::{
+    alloc::Layout,
+    collections::{HashMap, HashSet},
+};
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Default)]
 pub(crate) struct RelationsTable {
     outgoing: HashMap<AnyIndex, HashSet<AnyIndex>>,
     incoming: HashMap<AnyIndex, HashSet<AnyIndex>>,
+    generations: HashMap<(AnyIndex, AnyIndex), u64>,
+    payloads: Option<Arena>,
+    payload_indices: HashMap<(AnyIndex, AnyIndex), Index>,
 }
 
+impl std::fmt::Debug for RelationsTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelationsTable")
+            .field("outgoing", &self.outgoing)
+            .field("incoming", &self.incoming)
+            .field("generations", &self.generations)
+            .field("payloads", &self.payload_indices.len())
+            .finish()
+    }
+}
+
+impl PartialEq for RelationsTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.outgoing == other.outgoing && self.incoming == other.incoming
+    }
+}
+
+impl Eq for RelationsTable {}
+
 impl RelationsTable {
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.outgoing.reserve(additional);
+        self.incoming.reserve(additional);
+        self.generations.reserve(additional);
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.outgoing
+            .capacity()
+            .min(self.incoming.capacity())
+            .min(self.generations.capacity())
+    }
+
     pub(crate) fn insert(&mut self, from: AnyIndex, to: AnyIndex) {
+        self.insert_at(from, to, 0);
+    }
+
+    pub(crate) fn insert_at(&mut self, from: AnyIndex, to: AnyIndex, generation: u64) {
         self.outgoing.entry(from).or_default().insert(to);
         self.incoming.entry(to).or_default().insert(from);
+        self.generations.entry((from, to)).or_insert(generation);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.outgoing.is_empty() && self.incoming.is_empty()
     }
 
     pub(crate) fn remove(&mut self, from: AnyIndex, to: AnyIndex) {
         if let Some(set) = self.outgoing.get_mut(&from) {
             set.remove(&to);
+            if set.is_empty() {
+                self.outgoing.remove(&from);
+            }
         }
-        if let Some(set) = self.outgoing.get_mut(&to) {
+        if let Some(set) = self.incoming.get_mut(&to) {
             set.remove(&from);
+            if set.is_empty() {
+                self.incoming.remove(&to);
+            }
         }
+        self.generations.remove(&(from, to));
+        self.remove_payload(from, to);
     }
 
     pub(crate) fn remove_all(&mut self, from: AnyIndex) {
-        if let Some(set) = self.outgoing.get_mut(&from) {
-            for to in set.drain() {
+        if let Some(set) = self.outgoing.remove(&from) {
+            for to in set {
                 if let Some(set) = self.incoming.get_mut(&to) {
                     set.remove(&from);
+                    if set.is_empty() {
+                        self.incoming.remove(&to);
+                    }
                 }
+                self.generations.remove(&(from, to));
+                self.remove_payload(from, to);
             }
         }
     }
 
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(AnyIndex, AnyIndex) -> bool) {
+        let incoming = &mut self.incoming;
+        let generations = &mut self.generations;
+        let payloads = &mut self.payloads;
+        let payload_indices = &mut self.payload_indices;
+        self.outgoing.retain(|from, set| {
+            set.retain(|to| {
+                let keep = f(*from, *to);
+                if !keep {
+                    if let Some(set) = incoming.get_mut(to) {
+                        set.remove(from);
+                        if set.is_empty() {
+                            incoming.remove(to);
+                        }
+                    }
+                    generations.remove(&(*from, *to));
+                    if let Some(index) = payload_indices.remove(&(*from, *to))
+                        && let Some(payloads) = payloads.as_mut()
+                    {
+                        let _ = payloads.remove(index);
+                    }
+                }
+                keep
+            });
+            !set.is_empty()
+        });
+    }
+
+    pub(crate) fn set_payload<T>(&mut self, from: AnyIndex, to: AnyIndex, payload: T) {
+        self.remove_payload(from, to);
+        if let Ok(index) = self
+            .payloads
+            .get_or_insert_with(|| Arena::new::<T>(0))
+            .insert(payload)
+        {
+            self.payload_indices.insert((from, to), index);
+        }
+    }
+
+    /// # Safety
+    /// `source` must point to initialized value described by `type_hash` and
+    /// `layout`, which gets moved into payloads arena.
+    pub(crate) unsafe fn set_payload_raw(
+        &mut self,
+        from: AnyIndex,
+        to: AnyIndex,
+        type_hash: TypeHash,
+        layout: Layout,
+        finalizer: unsafe fn(*mut ()),
+        source: *const u8,
+    ) {
+        self.remove_payload(from, to);
+        let payloads = self
+            .payloads
+            .get_or_insert_with(|| unsafe { Arena::new_raw(type_hash, layout, finalizer, 0) });
+        unsafe {
+            let (index, memory) = payloads.allocate();
+            source.copy_to_nonoverlapping(memory, layout.size());
+            self.payload_indices.insert((from, to), index);
+        }
+    }
+
+    pub(crate) fn payload_ptr(&self, from: AnyIndex, to: AnyIndex) -> Option<*const u8> {
+        let index = self.payload_indices.get(&(from, to))?;
+        unsafe { self.payloads.as_ref()?.read_ptr(*index).ok() }
+    }
+
+    pub(crate) fn has_payloads(&self) -> bool {
+        !self.payload_indices.is_empty()
+    }
+
+    pub(crate) fn clear_payloads(&mut self) {
+        self.payloads = None;
+        self.payload_indices.clear();
+    }
+
+    /// Moves relation together with its payload to new endpoints. If relation
+    /// between new endpoints already exists, it keeps its own payload.
+    pub(crate) fn move_relation(
+        &mut self,
+        from: AnyIndex,
+        to: AnyIndex,
+        new_from: AnyIndex,
+        new_to: AnyIndex,
+        generation: u64,
+    ) {
+        let payload = self.payload_indices.remove(&(from, to));
+        self.remove(from, to);
+        if self.contains(new_from, new_to) {
+            if let Some(index) = payload
+                && let Some(payloads) = self.payloads.as_mut()
+            {
+                let _ = payloads.remove(index);
+            }
+        } else {
+            self.insert_at(new_from, new_to, generation);
+            if let Some(index) = payload {
+                self.payload_indices.insert((new_from, new_to), index);
+            }
+        }
+    }
+
+    pub(crate) fn remove_payload(&mut self, from: AnyIndex, to: AnyIndex) {
+        if let Some(index) = self.payload_indices.remove(&(from, to))
+            && let Some(payloads) = self.payloads.as_mut()
+        {
+            let _ = payloads.remove(index);
+        }
+    }
+
+    pub(crate) fn payload<T>(
+        &self,
+        from: AnyIndex,
+        to: AnyIndex,
+    ) -> Option<ValueReadAccess<'_, T>> {
+        let index = self.payload_indices.get(&(from, to))?;
+        self.payloads.as_ref()?.read(*index).ok()
+    }
+
+    pub(crate) fn payload_mut<T>(
+        &self,
+        from: AnyIndex,
+        to: AnyIndex,
+    ) -> Option<ValueWriteAccess<'_, T>> {
+        let index = self.payload_indices.get(&(from, to))?;
+        self.payloads.as_ref()?.write(*index).ok()
+    }
+
+    pub(crate) fn iter_since(
+        &self,
+        generation: u64,
+    ) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
+        self.generations
+            .iter()
+            .filter(move |(_, value)| **value > generation)
+            .map(|(pair, _)| *pair)
+    }
+
+    pub(crate) fn generation(&self, from: AnyIndex, to: AnyIndex) -> Option<u64> {
+        self.generations.get(&(from, to)).copied()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.outgoing.values().map(|set| set.len()).sum()
+    }
+
+    pub(crate) fn out_degree(&self, from: AnyIndex) -> usize {
+        self.outgoing
+            .get(&from)
+            .map(|set| set.len())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn in_degree(&self, to: AnyIndex) -> usize {
+        self.incoming
+            .get(&to)
+            .map(|set| set.len())
+            .unwrap_or_default()
+    }
+
     pub(crate) fn contains(&self, from: AnyIndex, to: AnyIndex) -> bool {
         self.outgoing
             .get(&from)
@@ -53,12 +277,35 @@ impl RelationsTable {
             .flat_map(|set| set.iter().copied())
     }
 
+    pub(crate) fn outgoing_set(&self, from: AnyIndex) -> Option<&HashSet<AnyIndex>> {
+        self.outgoing.get(&from).filter(|set| !set.is_empty())
+    }
+
+    pub(crate) fn incoming_set(&self, to: AnyIndex) -> Option<&HashSet<AnyIndex>> {
+        self.incoming.get(&to).filter(|set| !set.is_empty())
+    }
+
+    pub(crate) fn outgoing_degrees(&self) -> impl Iterator<Item = (AnyIndex, usize)> + '_ {
+        self.outgoing
+            .iter()
+            .filter(|(_, set)| !set.is_empty())
+            .map(|(from, set)| (*from, set.len()))
+    }
+
     pub(crate) fn iter_outgoing(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
         self.outgoing
             .iter()
             .flat_map(|(from, set)| set.iter().map(move |to| (*from, *to)))
     }
 
+    pub(crate) fn for_each_outgoing(&self, mut f: impl FnMut(AnyIndex, AnyIndex)) {
+        for (from, set) in &self.outgoing {
+            for to in set {
+                f(*from, *to);
+            }
+        }
+    }
+
     pub(crate) fn iter_incoming(&self) -> impl Iterator<Item = (AnyIndex, AnyIndex)> + '_ {
         self.incoming
             .iter()
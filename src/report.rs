@@ -0,0 +1,124 @@
+use crate::{graph::Graph, relations::RelationsTable, utils::type_name};
+use intuicio_core::registry::Registry;
+use intuicio_framework_arena::AnyIndex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Statistics of single relation category.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelationReport {
+    /// Number of relations.
+    pub edges: usize,
+    /// Number of weakly connected components formed by relations.
+    pub components: usize,
+    /// Whether relations contain a cycle.
+    pub has_cycle: bool,
+}
+
+/// Summary of graph content, meant to be logged or sent to telemetry.
+/// Types and relation categories are identified by their registry names,
+/// qualified with module name if they have one, or
+/// by type hash if they are not registered.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphReport {
+    /// Total number of nodes.
+    pub nodes: usize,
+    /// Number of nodes per type name.
+    pub nodes_per_type: BTreeMap<String, usize>,
+    /// Total number of relations.
+    pub edges: usize,
+    /// Statistics per relation category name.
+    pub relations: BTreeMap<String, RelationReport>,
+}
+
+impl Graph {
+    /// Creates report summarizing graph content.
+    ///
+    /// # Arguments
+    /// * `registry` - The registry to use for type names lookups.
+    ///
+    /// # Returns
+    /// A `GraphReport` of this graph.
+    pub fn report(&self, registry: &Registry) -> GraphReport {
+        let mut result = GraphReport::default();
+        for arena in self.nodes.arenas() {
+            if arena.is_empty() {
+                continue;
+            }
+            result.nodes += arena.len();
+            *result
+                .nodes_per_type
+                .entry(type_name(registry, arena.type_hash()))
+                .or_default() += arena.len();
+        }
+        for (type_hash, relations) in &self.relations {
+            let report = relation_report(relations);
+            if report.edges == 0 {
+                continue;
+            }
+            result.edges += report.edges;
+            result
+                .relations
+                .insert(type_name(registry, *type_hash), report);
+        }
+        result
+    }
+}
+
+fn relation_report(relations: &RelationsTable) -> RelationReport {
+    let edges = relations.iter_outgoing().collect::<Vec<_>>();
+    let mut parents = HashMap::<AnyIndex, AnyIndex>::default();
+    let mut degrees = HashMap::<AnyIndex, usize>::default();
+    for (from, to) in &edges {
+        parents.entry(*from).or_insert(*from);
+        parents.entry(*to).or_insert(*to);
+        let a = find_root(&mut parents, *from);
+        let b = find_root(&mut parents, *to);
+        if a != b {
+            parents.insert(a, b);
+        }
+        degrees.entry(*from).or_default();
+        *degrees.entry(*to).or_default() += 1;
+    }
+    let components = parents
+        .keys()
+        .copied()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter(|index| find_root(&mut parents, *index) == *index)
+        .count();
+    let mut queue = degrees
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(index, _)| *index)
+        .collect::<Vec<_>>();
+    let mut processed = 0;
+    while let Some(source) = queue.pop() {
+        processed += 1;
+        for target in relations.outgoing(source) {
+            if let Some(degree) = degrees.get_mut(&target) {
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push(target);
+                }
+            }
+        }
+    }
+    RelationReport {
+        edges: edges.len(),
+        components,
+        has_cycle: processed < degrees.len(),
+    }
+}
+
+fn find_root(parents: &mut HashMap<AnyIndex, AnyIndex>, mut index: AnyIndex) -> AnyIndex {
+    while let Some(parent) = parents.get(&index).copied() {
+        if parent == index {
+            break;
+        }
+        let grandparent = parents[&parent];
+        parents.insert(index, grandparent);
+        index = parent;
+    }
+    index
+}
@@ -0,0 +1,17 @@
+use intuicio_core::{registry::Registry, types::TypeQuery};
+use intuicio_data::type_hash::TypeHash;
+
+/// Gets name of type used in exports and reports, qualified with module name
+/// if type has one, or formatted type hash if type is not registered.
+pub(crate) fn type_name(registry: &Registry, type_hash: TypeHash) -> String {
+    registry
+        .find_type(TypeQuery {
+            type_hash: Some(type_hash),
+            ..Default::default()
+        })
+        .map(|type_| match type_.module_name() {
+            Some(module_name) => format!("{}::{}", module_name, type_.name()),
+            None => type_.name().to_owned(),
+        })
+        .unwrap_or_else(|| format!("{type_hash:?}"))
+}